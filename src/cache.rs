@@ -0,0 +1,177 @@
+use crate::config::Provider;
+use crate::error::PodError;
+use crate::picture_io::Metadata;
+
+use chrono::NaiveDate;
+use serde_json::{from_str, to_string_pretty};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Days a cached picture is kept after it was downloaded
+const CACHE_DAYS: u64 = 30;
+
+/// Identifies a picture of a day, the variant tells apart pictures of the same day,
+/// e.g. markets or resolutions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub date: NaiveDate,
+    pub variant: String,
+}
+
+impl CacheKey {
+    pub fn new(date: NaiveDate, variant: &str) -> Self {
+        // the variant is used as a file name
+        let variant = variant
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        CacheKey { date, variant }
+    }
+}
+
+/// Downloaded pictures and their metadata of a provider, kept as
+/// `<cache dir>/pod/<provider>/<date>/<variant>.picture` and `<variant>.json`
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Cache of a provider, none when there is no user cache directory
+    pub fn new(provider: &Provider) -> Option<Self> {
+        dirs::cache_dir().map(|dir| Cache {
+            dir: dir.join("pod").join(provider.to_string()),
+        })
+    }
+
+    fn get_paths(&self, key: &CacheKey) -> (PathBuf, PathBuf) {
+        let day_dir = self.dir.join(key.date.to_string());
+        (
+            day_dir.join(format!("{}.picture", key.variant)),
+            day_dir.join(format!("{}.json", key.variant)),
+        )
+    }
+
+    /// Cached picture and metadata, a missing or broken entry is a miss
+    pub fn load(&self, key: &CacheKey) -> Option<(Vec<u8>, Metadata)> {
+        let (picture_path, metadata_path) = self.get_paths(key);
+        let metadata = fs::read_to_string(&metadata_path)
+            .ok()
+            .and_then(|text| from_str::<Metadata>(&text).ok())?;
+        let bytes = fs::read(&picture_path).ok()?;
+        println!("Using cached picture {}", picture_path.display());
+        Some((bytes, metadata))
+    }
+
+    /// Stores a picture under its publication date when the provider returned one,
+    /// so a picture of yesterday fetched early in the day isn't taken for today's.
+    /// Entries downloaded more than a month ago are removed.
+    pub fn store(&self, key: &CacheKey, bytes: &[u8], metadata: &Metadata) -> Result<(), PodError> {
+        let key = CacheKey {
+            date: metadata.date.unwrap_or(key.date),
+            variant: key.variant.clone(),
+        };
+        let (picture_path, metadata_path) = self.get_paths(&key);
+        let save_error = |path: &PathBuf, err: std::io::Error| {
+            PodError::Save(format!("{}: {}", path.display(), err))
+        };
+        if let Some(day_dir) = picture_path.parent() {
+            fs::create_dir_all(day_dir).map_err(|err| save_error(&day_dir.to_path_buf(), err))?;
+        }
+        fs::write(&picture_path, bytes).map_err(|err| save_error(&picture_path, err))?;
+        // metadata last, an entry without it is a miss
+        let json = to_string_pretty(metadata).map_err(|err| PodError::Save(err.to_string()))?;
+        fs::write(&metadata_path, json).map_err(|err| save_error(&metadata_path, err))?;
+        println!("Cached picture as {}", picture_path.display());
+        self.prune();
+        Ok(())
+    }
+
+    /// Removes the days written last before the kept ones, old publication dates
+    /// like `nasa --date 2010-01-01` stay as long as recent ones
+    fn prune(&self) {
+        let Some(oldest) =
+            SystemTime::now().checked_sub(Duration::from_secs(CACHE_DAYS * 24 * 60 * 60))
+        else {
+            return;
+        };
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let is_day = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| NaiveDate::parse_from_str(name, "%Y-%m-%d").is_ok());
+            let expired = last_written(&entry.path()).is_some_and(|written| written < oldest);
+            if is_day && expired {
+                if let Err(err) = fs::remove_dir_all(entry.path()) {
+                    eprintln!("Failed to remove {}: {}", entry.path().display(), err);
+                }
+            }
+        }
+    }
+}
+
+/// Latest modification time of the files in a directory, of the directory when it is empty.
+/// Overwriting a file doesn't change the time of its directory.
+fn last_written(dir: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| modified(&entry.path()))
+        .max()
+        .or_else(|| modified(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(date: NaiveDate) -> Metadata {
+        Metadata {
+            title: Some("Old picture".to_string()),
+            description: None,
+            source_url: None,
+            date: Some(date),
+            content_hash: None,
+            provider: None,
+        }
+    }
+
+    #[test]
+    fn old_publication_dates_stay_cached() {
+        let dir = std::env::temp_dir().join(format!("pod-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = Cache { dir: dir.clone() };
+
+        // downloaded long ago
+        let expired = dir.join("2020-01-01");
+        fs::create_dir_all(&expired).unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs((CACHE_DAYS + 1) * 24 * 60 * 60);
+        for name in ["picture.picture", "picture.json"] {
+            let file = fs::File::create(expired.join(name)).unwrap();
+            file.set_modified(long_ago).unwrap();
+        }
+
+        let date = NaiveDate::from_ymd_opt(2010, 1, 1).unwrap();
+        let key = CacheKey::new(date, "picture");
+        cache.store(&key, b"picture", &metadata(date)).unwrap();
+        let (bytes, cached) = cache.load(&key).unwrap();
+        assert_eq!(bytes, b"picture");
+        assert_eq!(cached.date, Some(date));
+        assert!(!expired.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,11 +1,38 @@
-use clap::{Parser, Subcommand};
+use crate::config_file::ConfigFile;
+use crate::error::PodError;
+use crate::secret::Secret;
+
+use chrono::{NaiveDate, NaiveTime};
+use clap::{ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+pub const DEFAULT_WIKIMEDIA_API_URL: &str = "https://api.wikimedia.org/feed/v1/wikipedia/en/featured";
+pub const DEFAULT_BING_MARKET: &str = "en-US";
+pub const DEFAULT_EXEC_TIMEOUT: u64 = 60;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Config {
     #[command(subcommand)]
-    /// Select a provider
-    pub provider: Provider,
+    /// Select a provider or a command, the provider can also be set in the config file
+    pub command: Option<Command>,
+
+    #[arg(skip)]
+    /// Provider of the config file, used when the command line doesn't select one
+    pub provider: Option<Provider>,
+
+    #[arg(long)]
+    /// Config file, defaults to pod/config.toml in the user config directory
+    pub config: Option<PathBuf>,
+
+    #[arg(long)]
+    /// Profile of the config file, defaults to default_profile of the config file
+    pub profile: Option<String>,
+
+    #[arg(long, default_value = "false")]
+    /// Always download the picture, even when today's picture is cached
+    pub no_cache: bool,
 
     #[arg(long, short)]
     /// Add metadata to the image (title and description)
@@ -32,86 +59,627 @@ pub struct Config {
     #[arg(long)] 
     /// Target image height if fit screen size set to true
     pub height: Option<u32>,
+
+    #[arg(long, value_enum)]
+    /// How the picture is fitted to the screen size, defaults to fill
+    pub fit_mode: Option<FitMode>,
+
+    #[arg(long = "provider-fit-mode", value_name = "PROVIDER=MODE", value_parser = parse_provider_fit_mode)]
+    /// Fit mode of pictures of one provider, e.g. nasa=contain-blur. Can be repeated
+    pub provider_fit_modes: Vec<(String, FitMode)>,
+
+    #[arg(long)]
+    /// Skip pictures narrower than this
+    pub min_width: Option<u32>,
+
+    #[arg(long)]
+    /// Skip pictures lower than this
+    pub min_height: Option<u32>,
+
+    #[arg(long, value_parser = parse_aspect_ratio)]
+    /// Skip pictures with a lower width to height ratio, e.g. 4:3 or 1.33
+    pub min_aspect_ratio: Option<f32>,
+
+    #[arg(long, value_parser = parse_aspect_ratio)]
+    /// Skip pictures with a higher width to height ratio, e.g. 21:9 or 2.33
+    pub max_aspect_ratio: Option<f32>,
+
+    #[arg(long = "exclude-keyword", value_name = "KEYWORD")]
+    /// Skip pictures with this word in the title or description, case is ignored. Can be repeated
+    pub exclude_keywords: Vec<String>,
+
+    #[arg(long, value_name = "DAYS")]
+    /// Skip pictures looking like a wallpaper of the last N days, e.g. re-encoded copies
+    pub dedupe_days: Option<u32>,
+
+    #[arg(long, value_name = "BITS")]
+    /// Differing bits of the 64 bit perceptual hashes up to which pictures are duplicates, defaults to 5
+    pub dedupe_threshold: Option<u32>,
 }
 
 impl Config {
-    pub fn get_nasa_url(&self) -> String {
-        match &self.provider {
-            Provider::Nasa {
-                nasa_random,
-                nasa_api_key,
-            } => {
-                if *nasa_random {
-                    self.get_nasa_random_url()
-                } else {
-                    format!(
-                        "https://api.nasa.gov/planetary/apod?api_key={}",
-                        &nasa_api_key
-                    )
-                }
-            }
-            _ => "".to_string(),
-        }
+    /// Parses the command line and fills options not given there from the config file
+    pub fn load() -> Result<Config, PodError> {
+        let mut config = Config::parse();
+        ConfigFile::merge(&mut config)?;
+        Ok(config)
     }
 
-    pub fn get_nasa_random_url(&self) -> String {
-        format!(
-            "https://api.nasa.gov/planetary/apod?api_key={}&count=1",
-            match &self.provider {
-                Provider::Nasa {
-                    nasa_random: _,
-                    nasa_api_key,
-                } => {
-                    nasa_api_key
-                }
-                _ => panic!("Not supported"),
-            }
-        )
+    /// Selected provider, fails if neither the command line nor the config file selected one
+    pub fn get_provider(&self) -> Result<&Provider, PodError> {
+        let command_provider = match &self.command {
+            Some(Command::Provider(provider)) => Some(provider),
+            Some(Command::Daemon(args)) => args.provider.as_ref(),
+            _ => None,
+        };
+        command_provider.or(self.provider.as_ref()).ok_or_else(|| {
+            PodError::Config(
+                "No provider selected, pass one on the command line or set it in the config file"
+                    .to_string(),
+            )
+        })
     }
 
-    pub fn is_nasa_random(&self) -> bool {
-        match &self.provider {
-            Provider::Nasa {
-                nasa_random,
-                nasa_api_key: _,
-            } => {
-                *nasa_random
-            }
-            _ => false,
-        }
+    pub fn get_picture_file_name(&self) -> String {
+        let name = self
+            .get_provider()
+            .map_or("pod".to_string(), |provider| provider.to_string());
+        self.get_state_file_name(&format!("{}.jpg", name))
     }
 
-    pub fn get_picture_file_name(&self) -> String {
+    /// Fit mode of a picture, the one of its provider if set
+    pub fn get_fit_mode(&self, provider: Option<&str>) -> FitMode {
+        self.provider_fit_modes
+            .iter()
+            .find(|(name, _)| Some(name.as_str()) == provider)
+            .map(|(_, mode)| *mode)
+            .or(self.fit_mode)
+            .unwrap_or_default()
+    }
+
+    /// Path of a file kept next to the saved picture
+    pub fn get_state_file_name(&self, file_name: &str) -> String {
         let current_dir = std::env::current_dir().unwrap_or_else(|err| {
             eprintln!("Error, current directory is not available: {:?}", err);
             // get temp directory
             std::env::temp_dir()
         });
-        current_dir.join(format!("{}.jpg", self.provider)).to_str().unwrap().to_string()
+        current_dir.join(file_name).to_string_lossy().to_string()
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    #[command(flatten)]
+    Provider(Provider),
+    /// Stay resident and refresh the wallpaper on a schedule
+    Daemon(DaemonArgs),
+    /// Write a systemd user service and timer running pod with the given options
+    InstallTimer(TimerArgs),
+    /// Remove the systemd user service and timer
+    UninstallTimer(UnitArgs),
+    /// List, search and show the wallpapers set before
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Set the wallpaper set before the current one again
+    Previous(ReapplyArgs),
+    /// Set the wallpaper set after the current one again
+    Next(ReapplyArgs),
+    /// Set a wallpaper of the history again
+    Apply {
+        /// Id of the history entry, see pod history list
+        id: u64,
+
+        #[command(flatten)]
+        args: ReapplyArgs,
+    },
+    /// Add the current wallpaper to the favorites, used by the favorites provider
+    Favorite(MarkArgs),
+    /// Skip the picture of the current wallpaper in all future runs
+    Ban(MarkArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MarkArgs {
+    /// Id of the history entry, defaults to the current wallpaper
+    pub id: Option<u64>,
+
+    #[arg(long, default_value = "false")]
+    /// Remove the mark instead
+    pub remove: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ReapplyArgs {
+    #[arg(long, default_value = "false")]
+    /// Apply the modifiers of the command line to the original picture
+    /// instead of setting the wallpaper as it was
+    pub remodify: bool,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum HistoryCommand {
+    /// List the latest wallpapers, newest first
+    List {
+        #[arg(long, short = 'n', default_value_t = 20)]
+        /// Number of entries
+        limit: usize,
+
+        #[arg(long)]
+        /// Only wallpapers set on this day, e.g. 2024-04-08
+        date: Option<NaiveDate>,
+    },
+    /// Find wallpapers by title, description, provider or source url
+    Search {
+        /// Text to find, case is ignored
+        text: String,
+
+        #[arg(long, short = 'n', default_value_t = 20)]
+        /// Number of entries
+        limit: usize,
+    },
+    /// Show all details of a wallpaper
+    Show {
+        /// Id of the entry as listed
+        id: u64,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+#[command(group(ArgGroup::new("schedule").required(true).args(["at", "every", "on_new"])))]
+pub struct DaemonArgs {
+    #[arg(long, value_parser = parse_time)]
+    /// Refresh daily at this local time, e.g. 07:30
+    pub at: Option<NaiveTime>,
+
+    #[arg(long, value_name = "HOURS", value_parser = clap::value_parser!(u64).range(1..))]
+    /// Refresh every N hours, starting right away
+    pub every: Option<u64>,
+
+    #[arg(long, default_value = "false")]
+    /// Refresh when the provider publishes a new picture, checked every poll interval
+    pub on_new: bool,
+
+    #[arg(long, value_name = "MINUTES", default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    /// Poll interval of --on-new
+    pub poll: u64,
+
+    #[arg(long, default_value = "false")]
+    /// Also refresh once at startup when using --at
+    pub startup: bool,
+
+    #[arg(long, default_value_t = 5)]
+    /// Retries of a failed refresh before waiting for the next scheduled one
+    pub retries: u32,
+
+    #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+    /// Delay before the first retry, doubled on every further retry up to an hour
+    pub retry_delay: u64,
+
+    #[command(subcommand)]
+    /// Provider, can also be set in the config file
+    pub provider: Option<Provider>,
+}
+
+#[derive(Clone, Args)]
+pub struct TimerArgs {
+    #[arg(long, default_value = "daily")]
+    /// When to refresh, a systemd calendar expression, e.g. daily or "*-*-* 07:30"
+    pub on_calendar: String,
+
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    /// Catch up on a refresh missed while the machine was off
+    pub persistent: bool,
+
+    #[command(flatten)]
+    pub unit: UnitArgs,
+
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    /// Provider and its arguments, can be left out when set in the config file
+    pub provider: Vec<String>,
+}
+
+impl std::fmt::Debug for TimerArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // the provider words may contain an api key, shown parsed the secrets are redacted
+        let provider = (!self.provider.is_empty()).then(|| {
+            ProviderSpec::try_parse_from(&self.provider)
+                .map(|spec| spec.provider)
+                .map_err(|_| "invalid provider arguments")
+        });
+        f.debug_struct("TimerArgs")
+            .field("on_calendar", &self.on_calendar)
+            .field("persistent", &self.persistent)
+            .field("unit", &self.unit)
+            .field("provider", &provider)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct UnitArgs {
+    #[arg(long)]
+    /// Directory of the units, defaults to systemd/user in the user config directory
+    pub unit_dir: Option<PathBuf>,
+
+    #[arg(long, default_value = "pod")]
+    /// Name of the service and timer units
+    pub unit_name: String,
+}
+
+fn parse_provider_fit_mode(value: &str) -> Result<(String, FitMode), String> {
+    let (provider, mode) = value
+        .split_once('=')
+        .ok_or_else(|| "expected <provider>=<mode>, e.g. nasa=contain".to_string())?;
+    check_provider_name(provider)?;
+    let mode = FitMode::from_str(mode, true)?;
+    Ok((provider.to_string(), mode))
+}
+
+/// Fails for names that are not a provider, e.g. typos in per provider options
+pub fn check_provider_name(name: &str) -> Result<(), String> {
+    let command = ProviderSpec::command();
+    let names: Vec<&str> = command.get_subcommands().map(|c| c.get_name()).collect();
+    if names.contains(&name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "unknown provider {}, expected one of {}",
+            name,
+            names.join(", ")
+        ))
+    }
+}
+
+/// Ratio given as `<width>:<height>` or as a number
+fn parse_aspect_ratio(value: &str) -> Result<f32, String> {
+    let ratio = match value.split_once(':') {
+        Some((width, height)) => {
+            let width: f32 = width.trim().parse().map_err(|_| "invalid width".to_string())?;
+            let height: f32 = height.trim().parse().map_err(|_| "invalid height".to_string())?;
+            width / height
+        }
+        None => value.parse().map_err(|_| "expected e.g. 16:9 or 1.78".to_string())?,
+    };
+    if ratio.is_finite() && ratio > 0.0 {
+        Ok(ratio)
+    } else {
+        Err("ratio must be greater than zero".to_string())
     }
 }
 
-#[derive(Debug, Subcommand)]
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|err| format!("expected HH:MM, {}", err))
+}
+
+#[derive(Debug, Clone, Subcommand, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum Provider {
     /// Nasa picture of the day
     Nasa {
         #[arg(long, default_value = "false")]
+        #[serde(default)]
         /// Set a random picture of the day
         nasa_random: bool,
         
-        #[arg(long, short)]
+        #[arg(long, short, env = "POD_NASA_API_KEY", hide_env_values = true)]
         /// Nasa api key, get at: https://api.nasa.gov/
-        nasa_api_key: String,
+        nasa_api_key: Option<Secret>,
+
+        #[arg(long, env = "POD_NASA_API_KEY_FILE")]
+        /// File containing the nasa api key, used when the key is not given directly
+        nasa_api_key_file: Option<PathBuf>,
+
+        #[arg(long, conflicts_with_all = ["nasa_random", "start_date"])]
+        /// Set the picture of a specific day, e.g. 2024-04-08
+        date: Option<NaiveDate>,
+
+        #[arg(long, conflicts_with = "nasa_random")]
+        /// Set a random picture from the days starting at this date
+        start_date: Option<NaiveDate>,
+
+        #[arg(long, requires = "start_date")]
+        /// Last day of the range, defaults to today
+        end_date: Option<NaiveDate>,
+
+        #[arg(long, default_value = "false")]
+        #[serde(default)]
+        /// On video days use the video thumbnail instead of a random picture
+        video_thumbnail: bool,
     },
     /// Bing picture of the day
-    Bing,
+    Bing {
+        #[arg(long, short, default_value = DEFAULT_BING_MARKET)]
+        #[serde(default = "default_bing_market")]
+        /// Market of the picture, title and copyright, e.g. de-DE or ja-JP
+        market: String,
+
+        #[arg(long, short, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=7))]
+        #[serde(default)]
+        /// Days back from today, 0 is today
+        idx: u8,
+
+        #[arg(long, short, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=8))]
+        #[serde(default = "default_one")]
+        /// Number of days fetched starting at idx going back, one of them is picked at random
+        count: u8,
+
+        #[arg(long, short, value_enum, default_value_t = BingResolution::Auto)]
+        #[serde(default)]
+        /// Picture resolution, auto picks the best match for width and height
+        resolution: BingResolution,
+    },
+    /// Wikimedia Commons picture of the day
+    Wikimedia {
+        #[arg(long, default_value = DEFAULT_WIKIMEDIA_API_URL)]
+        #[serde(default = "default_wikimedia_api_url")]
+        /// Featured content api url, can point to a mirror or a local server
+        wikimedia_api_url: String,
+    },
+    /// Picture from a local directory, works without network
+    Local {
+        #[arg(long, short)]
+        /// Directory containing the pictures
+        dir: PathBuf,
+
+        #[arg(long, short, value_enum, default_value_t = LocalOrder::Random)]
+        #[serde(default)]
+        /// How the picture is picked from the directory
+        order: LocalOrder,
+    },
+    /// Picture marked with pod favorite
+    Favorites {
+        #[arg(long, short, value_enum, default_value_t = LocalOrder::Random)]
+        #[serde(default)]
+        /// How the picture is picked from the favorites
+        order: LocalOrder,
+    },
+    /// Picture from any json api, fields are selected with paths like `$.images[0].url`
+    Json {
+        #[arg(long, short)]
+        /// Url of the json api
+        url: String,
+
+        #[arg(long)]
+        /// Path to the picture url, relative urls are resolved against the api url
+        image_path: String,
+
+        #[arg(long)]
+        /// Path to the picture title
+        title_path: Option<String>,
+
+        #[arg(long)]
+        /// Path to the picture description
+        description_path: Option<String>,
+    },
+    /// Picture from an RSS or Atom feed
+    Feed {
+        #[arg(long, short)]
+        /// Url of the feed
+        url: String,
+
+        #[arg(long, short, value_enum, default_value_t = FeedPick::Newest)]
+        #[serde(default)]
+        /// Which feed item is used
+        pick: FeedPick,
+    },
+    /// Picture from an external command printing
+    /// `{"image": "<path or url>", "title": "...", "description": "...", "link": "..."}`
+    Exec {
+        #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_EXEC_TIMEOUT, value_parser = clap::value_parser!(u64).range(1..))]
+        #[serde(default = "default_exec_timeout")]
+        /// Time the command may take before it is stopped
+        timeout: u64,
+
+        /// Command to run
+        command: String,
+
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        #[serde(default)]
+        /// Arguments passed to the command
+        args: Vec<String>,
+    },
+    /// Try providers in order until one returns a usable picture,
+    /// e.g. `fallback "nasa --nasa-api-key <key>" bing "local --dir ~/Pictures"`
+    Fallback {
+        #[arg(required = true, value_parser = parse_provider_spec)]
+        /// Providers with their arguments, each quoted as one argument
+        providers: Vec<Provider>,
+    },
+    /// Pick a provider at random on every run,
+    /// e.g. `random "60:bing" "30:nasa --nasa-api-key <key>" "10:local --dir ~/Pictures"`
+    Random {
+        #[arg(required = true, value_parser = parse_weighted_provider_spec)]
+        /// Providers with their arguments prefixed by a weight, each quoted as one argument.
+        /// Providers without a weight have a weight of 1
+        providers: Vec<WeightedProvider>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeightedProvider {
+    #[serde(default = "default_one")]
+    pub weight: u32,
+    #[serde(flatten)]
+    pub provider: Provider,
+}
+
+fn default_one<T: From<u8>>() -> T {
+    T::from(1)
+}
+
+fn default_bing_market() -> String {
+    DEFAULT_BING_MARKET.to_string()
+}
+
+fn default_wikimedia_api_url() -> String {
+    DEFAULT_WIKIMEDIA_API_URL.to_string()
+}
+
+fn default_exec_timeout() -> u64 {
+    DEFAULT_EXEC_TIMEOUT
+}
+
+/// Parser for a single provider given as one argument, used by providers wrapping other providers
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct ProviderSpec {
+    #[command(subcommand)]
+    provider: Provider,
+}
+
+fn parse_provider_spec(spec: &str) -> Result<Provider, String> {
+    let words = shell_words::split(spec).map_err(|err| err.to_string())?;
+    ProviderSpec::try_parse_from(words)
+        .map(|spec| spec.provider)
+        .map_err(|err| err.to_string())
+}
+
+fn parse_weighted_provider_spec(spec: &str) -> Result<WeightedProvider, String> {
+    let (weight, spec) = spec
+        .split_once(':')
+        .and_then(|(weight, rest)| weight.trim().parse::<u32>().ok().map(|w| (w, rest)))
+        .unwrap_or((1, spec));
+    Ok(WeightedProvider {
+        weight,
+        provider: parse_provider_spec(spec)?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BingResolution {
+    /// Smallest resolution covering width and height, 1920x1080 if they are not set
+    #[default]
+    Auto,
+    /// Original resolution, usually 3840x2160 or larger
+    Uhd,
+    #[value(name = "1920x1200")]
+    #[serde(rename = "1920x1200")]
+    R1920x1200,
+    #[value(name = "1920x1080")]
+    #[serde(rename = "1920x1080")]
+    R1920x1080,
+    #[value(name = "1366x768")]
+    #[serde(rename = "1366x768")]
+    R1366x768,
+    #[value(name = "1280x768")]
+    #[serde(rename = "1280x768")]
+    R1280x768,
+    #[value(name = "1024x768")]
+    #[serde(rename = "1024x768")]
+    R1024x768,
+    /// Portrait
+    #[value(name = "1080x1920")]
+    #[serde(rename = "1080x1920")]
+    R1080x1920,
+    /// Portrait
+    #[value(name = "768x1280")]
+    #[serde(rename = "768x1280")]
+    R768x1280,
+}
+
+impl BingResolution {
+    /// Fixed resolutions ordered from small to large, portrait ones last
+    pub const SIZES: [BingResolution; 7] = [
+        BingResolution::R1024x768,
+        BingResolution::R1280x768,
+        BingResolution::R1366x768,
+        BingResolution::R1920x1080,
+        BingResolution::R1920x1200,
+        BingResolution::R768x1280,
+        BingResolution::R1080x1920,
+    ];
+
+    /// Width and height of fixed resolutions
+    pub fn size(&self) -> Option<(u32, u32)> {
+        match self {
+            BingResolution::Auto | BingResolution::Uhd => None,
+            BingResolution::R1920x1200 => Some((1920, 1200)),
+            BingResolution::R1920x1080 => Some((1920, 1080)),
+            BingResolution::R1366x768 => Some((1366, 768)),
+            BingResolution::R1280x768 => Some((1280, 768)),
+            BingResolution::R1024x768 => Some((1024, 768)),
+            BingResolution::R1080x1920 => Some((1080, 1920)),
+            BingResolution::R768x1280 => Some((768, 1280)),
+        }
+    }
+
+    /// Suffix appended to the picture urlbase, e.g. `_UHD.jpg`
+    pub fn suffix(&self) -> String {
+        match self.size() {
+            Some((width, height)) => format!("_{}x{}.jpg", width, height),
+            None => "_UHD.jpg".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedPick {
+    /// Most recently published item
+    #[default]
+    Newest,
+    /// Random item of the feed
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalOrder {
+    /// Random picture on every run
+    #[default]
+    Random,
+    /// Next picture in file name order on every run
+    Sequential,
+    /// Picture whose file name contains today's date (2025-02-14, 20250214 or 02-14)
+    Date,
+}
+
+/// How the picture is fitted to the screen size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FitMode {
+    /// Scale to cover the screen, cropping what doesn't fit
+    #[default]
+    Fill,
+    /// Like fill, but the crop keeps the most detailed region instead of the center
+    SmartFill,
+    /// Scale to fit the screen, with black bars
+    Contain,
+    /// Scale to fit the screen, with a blurred copy of the picture behind it
+    ContainBlur,
+    /// Scale to the screen size ignoring the aspect ratio
+    Stretch,
+    /// Keep the size and center the picture
+    Center,
+    /// Repeat the picture at its size
+    Tile,
+}
+
+impl std::fmt::Display for FitMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = self
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
 }
 
 impl std::fmt::Display for Provider {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
             Provider::Nasa { .. } => write!(f, "nasa"),
-            Provider::Bing => write!(f, "bing"),
+            Provider::Bing { .. } => write!(f, "bing"),
+            Provider::Wikimedia { .. } => write!(f, "wikimedia"),
+            Provider::Local { .. } => write!(f, "local"),
+            Provider::Favorites { .. } => write!(f, "favorites"),
+            Provider::Json { .. } => write!(f, "json"),
+            Provider::Feed { .. } => write!(f, "feed"),
+            Provider::Exec { .. } => write!(f, "exec"),
+            Provider::Fallback { .. } => write!(f, "fallback"),
+            Provider::Random { .. } => write!(f, "random"),
         }
     }
 }
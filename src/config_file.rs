@@ -0,0 +1,141 @@
+use crate::config::{check_provider_name, Config, FitMode, Provider};
+use crate::error::PodError;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Options that can be set at the top level of the config file and in each profile
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Settings {
+    pub provider: Option<Provider>,
+    pub add_metadata: Option<bool>,
+    pub metadata_width: Option<f32>,
+    pub metadata_font: Option<String>,
+    pub metadata_font_size: Option<f32>,
+    pub fit_to_screen_size: Option<bool>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fit_mode: Option<FitMode>,
+    /// Fit modes by provider name, e.g. `provider_fit_modes = { nasa = "contain-blur" }`
+    #[serde(default)]
+    pub provider_fit_modes: HashMap<String, FitMode>,
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub min_aspect_ratio: Option<f32>,
+    pub max_aspect_ratio: Option<f32>,
+    #[serde(default)]
+    pub exclude_keywords: Vec<String>,
+    pub dedupe_days: Option<u32>,
+    pub dedupe_threshold: Option<u32>,
+}
+
+impl Settings {
+    /// Fails for options a command line parser would have rejected
+    fn check(&self) -> Result<(), String> {
+        self.provider_fit_modes
+            .keys()
+            .try_for_each(|name| check_provider_name(name))
+    }
+
+    /// Fills the options of the config that are not set yet
+    fn apply_to(self, config: &mut Config) {
+        config.provider = config.provider.take().or(self.provider);
+        config.add_metadata = config.add_metadata.or(self.add_metadata);
+        config.metadata_width = config.metadata_width.or(self.metadata_width);
+        config.metadata_font = config.metadata_font.take().or(self.metadata_font);
+        config.metadata_font_size = config.metadata_font_size.or(self.metadata_font_size);
+        config.fit_to_screen_size = config.fit_to_screen_size.or(self.fit_to_screen_size);
+        config.width = config.width.or(self.width);
+        config.height = config.height.or(self.height);
+        config.fit_mode = config.fit_mode.or(self.fit_mode);
+        // modes given before win, the first matching one is used
+        config.provider_fit_modes.extend(self.provider_fit_modes);
+        config.min_width = config.min_width.or(self.min_width);
+        config.min_height = config.min_height.or(self.min_height);
+        config.min_aspect_ratio = config.min_aspect_ratio.or(self.min_aspect_ratio);
+        config.max_aspect_ratio = config.max_aspect_ratio.or(self.max_aspect_ratio);
+        if config.exclude_keywords.is_empty() {
+            config.exclude_keywords = self.exclude_keywords;
+        }
+        config.dedupe_days = config.dedupe_days.or(self.dedupe_days);
+        config.dedupe_threshold = config.dedupe_threshold.or(self.dedupe_threshold);
+    }
+}
+
+/// TOML config file, e.g.
+///
+/// ```toml
+/// default_profile = "desktop"
+/// add_metadata = true
+///
+/// [provider]
+/// type = "bing"
+/// market = "de-DE"
+///
+/// [profiles.desktop]
+/// fit_to_screen_size = true
+/// width = 2560
+/// height = 1440
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    /// Profile used when none is given on the command line
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Settings>,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+
+impl ConfigFile {
+    /// Default location, `pod/config.toml` in the user config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("pod").join("config.toml"))
+    }
+
+    pub fn read(path: &PathBuf) -> Result<ConfigFile, PodError> {
+        let text = fs::read_to_string(path).map_err(|err| {
+            PodError::Config(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        toml::from_str(&text)
+            .map_err(|err| PodError::Config(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Fills the options not given on the command line, the profile wins over the top level.
+    /// A missing default config file is not an error, a missing `--config` file is.
+    pub fn merge(config: &mut Config) -> Result<(), PodError> {
+        let path = match &config.config {
+            Some(path) => Some(path.clone()),
+            None => Self::default_path().filter(|path| path.is_file()),
+        };
+        let Some(path) = path else {
+            return match &config.profile {
+                Some(profile) => Err(PodError::Config(format!(
+                    "Profile {} requested but there is no config file",
+                    profile
+                ))),
+                None => Ok(()),
+            };
+        };
+        // stderr, the output of history listings is kept clean
+        eprintln!("Reading config file {}", path.display());
+        let mut file = Self::read(&path)?;
+
+        if let Some(name) = config.profile.clone().or(file.default_profile.take()) {
+            let profile = file.profiles.remove(&name).ok_or_else(|| {
+                PodError::Config(format!("Unknown profile {} in {}", name, path.display()))
+            })?;
+            profile
+                .check()
+                .map_err(|err| PodError::Config(format!("{}: {}", path.display(), err)))?;
+            profile.apply_to(config);
+        }
+        file.settings
+            .check()
+            .map_err(|err| PodError::Config(format!("{}: {}", path.display(), err)))?;
+        file.settings.apply_to(config);
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config::{Config, DaemonArgs};
+use crate::error::PodError;
+use crate::pipeline;
+
+use chrono::{Local, NaiveTime, TimeDelta};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::thread;
+use std::time::Duration;
+
+/// Longest delay between two retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Refreshes the wallpaper on the schedule of the daemon arguments, only returns on configuration errors.
+/// Failed refreshes are retried with backoff, then skipped until the next scheduled one.
+pub fn run(config: &Config, args: &DaemonArgs) -> Result<(), PodError> {
+    // fail right away instead of on every refresh
+    config.get_provider()?;
+
+    if let Some(at) = args.at {
+        if args.startup {
+            refresh(config, args)?;
+        }
+        loop {
+            sleep_until(at);
+            refresh(config, args)?;
+        }
+    } else if let Some(hours) = args.every {
+        loop {
+            refresh(config, args)?;
+            sleep(Duration::from_secs(hours.saturating_mul(60 * 60)));
+        }
+    } else {
+        let mut last_key = None;
+        loop {
+            match with_retry(args, || refresh_if_new(config, last_key)) {
+                Ok(key) => last_key = Some(key),
+                Err(err @ PodError::Config(_)) => return Err(err),
+                Err(err) => eprintln!("Giving up until next poll: {}", err),
+            }
+            sleep(Duration::from_secs(args.poll.saturating_mul(60)));
+        }
+    }
+}
+
+/// Runs the whole pipeline, configuration errors end the daemon
+fn refresh(config: &Config, args: &DaemonArgs) -> Result<(), PodError> {
+    match with_retry(args, || pipeline::run(config)) {
+        Ok(path) => println!("Wallpaper refreshed from {}", path),
+        Err(err @ PodError::Config(_)) => return Err(err),
+        Err(err) => eprintln!("Giving up until next refresh: {}", err),
+    }
+    Ok(())
+}
+
+/// Fetches the picture and only applies it when it differs from the last one,
+/// returns the key of the current picture
+fn refresh_if_new(config: &Config, last_key: Option<u64>) -> Result<u64, PodError> {
+    let (image, metadata) = pipeline::fetch(config)?;
+    // the source url identifies a picture, the pixels are compared when there is none
+    let mut hasher = DefaultHasher::new();
+    match &metadata.source_url {
+        Some(source_url) => source_url.hash(&mut hasher),
+        None => image.as_bytes().hash(&mut hasher),
+    }
+    let key = hasher.finish();
+    if last_key == Some(key) {
+        println!("No new picture");
+    } else {
+        let path = pipeline::apply(config, image, metadata)?;
+        println!("Wallpaper refreshed from {}", path);
+    }
+    Ok(key)
+}
+
+/// Calls f until it succeeds or the retries are used up, the delay doubles after every failure.
+/// Configuration errors are not retried.
+fn with_retry<T>(
+    args: &DaemonArgs,
+    mut f: impl FnMut() -> Result<T, PodError>,
+) -> Result<T, PodError> {
+    let mut delay = Duration::from_secs(args.retry_delay).min(MAX_RETRY_DELAY);
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(err @ PodError::Config(_)) => return Err(err),
+            Err(err) if attempt >= args.retries => return Err(err),
+            Err(err) => {
+                attempt += 1;
+                eprintln!(
+                    "{}, retry {} of {} in {}s",
+                    err,
+                    attempt,
+                    args.retries,
+                    delay.as_secs()
+                );
+                thread::sleep(delay);
+                delay = delay.saturating_mul(2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Sleeps until the next occurrence of a local time
+fn sleep_until(at: NaiveTime) {
+    let now = Local::now().naive_local();
+    let mut next = now.date().and_time(at);
+    if next <= now {
+        next += TimeDelta::days(1);
+    }
+    println!("Next refresh at {}", next);
+    sleep((next - now).to_std().unwrap_or_default());
+}
+
+fn sleep(duration: Duration) {
+    println!("Sleeping for {}s", duration.as_secs());
+    thread::sleep(duration);
+}
@@ -0,0 +1,74 @@
+use crate::modifiers::ImageModifierError;
+
+use std::error::Error;
+use std::fmt;
+
+/// Errors of a pod run, each failure class exits with its own code
+#[derive(Debug)]
+pub enum PodError {
+    /// Missing or invalid configuration
+    Config(String),
+    /// Request could not be sent or its response could not be read
+    Network(String),
+    /// Server answered with an unsuccessful status
+    HttpStatus { url: String, status: u16 },
+    /// Response could not be parsed, e.g. invalid json
+    Parse(String),
+    /// Provider could not produce a picture, e.g. no pictures in a directory
+    Provider(String),
+    /// Picture could not be decoded
+    ImageDecode(String),
+    /// Modifier failed to change the picture
+    Modifier(ImageModifierError),
+    /// Picture could not be saved
+    Save(String),
+    /// Wallpaper could not be set
+    Wallpaper(String),
+    /// History could not be read or written, or has no such entry
+    History(String),
+}
+
+impl PodError {
+    /// Process exit code, 1 and 2 are left to panics and argument errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PodError::Config(_) => 3,
+            PodError::Network(_) => 4,
+            PodError::HttpStatus { .. } => 5,
+            PodError::Parse(_) => 6,
+            PodError::Provider(_) => 7,
+            PodError::ImageDecode(_) => 8,
+            PodError::Modifier(_) => 9,
+            PodError::Save(_) => 10,
+            PodError::Wallpaper(_) => 11,
+            PodError::History(_) => 12,
+        }
+    }
+}
+
+impl fmt::Display for PodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PodError::Config(msg) => write!(f, "Configuration error: {}", msg),
+            PodError::Network(msg) => write!(f, "Network error: {}", msg),
+            PodError::HttpStatus { url, status } => {
+                write!(f, "Request to {} failed with status {}", url, status)
+            }
+            PodError::Parse(msg) => write!(f, "Failed to parse response: {}", msg),
+            PodError::Provider(msg) => write!(f, "Provider error: {}", msg),
+            PodError::ImageDecode(msg) => write!(f, "Failed to decode picture: {}", msg),
+            PodError::Modifier(err) => write!(f, "Failed to modify picture: {}", err),
+            PodError::Save(msg) => write!(f, "Failed to save picture: {}", msg),
+            PodError::Wallpaper(msg) => write!(f, "Failed to set wallpaper: {}", msg),
+            PodError::History(msg) => write!(f, "History error: {}", msg),
+        }
+    }
+}
+
+impl Error for PodError {}
+
+impl From<ImageModifierError> for PodError {
+    fn from(err: ImageModifierError) -> Self {
+        PodError::Modifier(err)
+    }
+}
@@ -0,0 +1,325 @@
+use crate::config::Config;
+use crate::error::PodError;
+use crate::history::{BannedPicture, History, HistoryEntry};
+use crate::picture_io::Metadata;
+
+use chrono::{DateTime, Local, TimeDelta};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+/// Differing bits up to which pictures are duplicates
+const DEFAULT_DEDUPE_THRESHOLD: u32 = 5;
+
+/// Decides whether a fetched picture may be used, rejected pictures are fetched again
+/// from providers returning different pictures
+pub trait PictureFilter {
+    /// Why the picture is rejected, none when it may be used
+    fn reject(&self, bytes: &[u8], metadata: &Metadata) -> Option<String>;
+}
+
+/// Filters applied to the pictures of all providers
+pub fn get_filters(config: &Config) -> Result<Vec<Box<dyn PictureFilter>>, PodError> {
+    let banned = History::open()?.banned()?;
+    let mut filters: Vec<Box<dyn PictureFilter>> = Vec::new();
+    if !banned.is_empty() {
+        filters.push(Box::new(BanFilter { banned }));
+    }
+    let has_size_limits = config.min_width.is_some()
+        || config.min_height.is_some()
+        || config.min_aspect_ratio.is_some()
+        || config.max_aspect_ratio.is_some();
+    if has_size_limits {
+        filters.push(Box::new(SizeFilter {
+            min_width: config.min_width.unwrap_or(0),
+            min_height: config.min_height.unwrap_or(0),
+            min_aspect_ratio: config.min_aspect_ratio.unwrap_or(0.0),
+            max_aspect_ratio: config.max_aspect_ratio.unwrap_or(f32::INFINITY),
+        }));
+    }
+    if let Some(days) = config.dedupe_days {
+        let history = History::open()?;
+        // no lower bound when the days reach back before the earliest representable date
+        let since =
+            TimeDelta::try_days(days.into()).and_then(|days| Local::now().checked_sub_signed(days));
+        filters.push(Box::new(DuplicateFilter::new(
+            history.entries()?,
+            history.current()?,
+            since,
+            config.dedupe_threshold.unwrap_or(DEFAULT_DEDUPE_THRESHOLD),
+        )));
+    }
+    if !config.exclude_keywords.is_empty() {
+        filters.push(Box::new(KeywordFilter {
+            keywords: config
+                .exclude_keywords
+                .iter()
+                .map(|keyword| words(keyword))
+                .filter(|words| !words.is_empty())
+                .collect(),
+        }));
+    }
+    Ok(filters)
+}
+
+/// Perceptual difference hash: the picture is shrunk to 9x8 gray pixels,
+/// each bit tells whether a pixel is brighter than its right neighbour
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(brighter);
+        }
+    }
+    hash
+}
+
+/// Reason of the first filter rejecting the picture
+pub fn reject(
+    filters: &[Box<dyn PictureFilter>],
+    bytes: &[u8],
+    metadata: &Metadata,
+) -> Option<String> {
+    filters
+        .iter()
+        .find_map(|filter| filter.reject(bytes, metadata))
+}
+
+/// Sha-256 of the downloaded picture as hex, identifies a picture served from different urls
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Rejects pictures banned with `pod ban`
+struct BanFilter {
+    banned: Vec<BannedPicture>,
+}
+
+impl PictureFilter for BanFilter {
+    fn reject(&self, bytes: &[u8], metadata: &Metadata) -> Option<String> {
+        let hash = metadata
+            .content_hash
+            .clone()
+            .unwrap_or_else(|| content_hash(bytes));
+        self.banned
+            .iter()
+            .any(|banned| {
+                banned.content_hash.as_ref() == Some(&hash)
+                    || (banned.source_url.is_some() && banned.source_url == metadata.source_url)
+            })
+            .then(|| "banned".to_string())
+    }
+}
+
+/// Rejects pictures that are too small or too narrow or wide
+struct SizeFilter {
+    min_width: u32,
+    min_height: u32,
+    min_aspect_ratio: f32,
+    max_aspect_ratio: f32,
+}
+
+impl PictureFilter for SizeFilter {
+    fn reject(&self, bytes: &[u8], metadata: &Metadata) -> Option<String> {
+        let _ = metadata;
+        // only the header is read, pictures that can't be read are rejected by the decoder later
+        let (width, height) = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()?
+            .into_dimensions()
+            .ok()?;
+        let aspect_ratio = width as f32 / height.max(1) as f32;
+        if width < self.min_width || height < self.min_height {
+            Some(format!("{}x{} is too small", width, height))
+        } else if aspect_ratio < self.min_aspect_ratio || aspect_ratio > self.max_aspect_ratio {
+            Some(format!(
+                "aspect ratio {:.2} of {}x{}",
+                aspect_ratio, width, height
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Lower case words of a text, anything but letters and digits separates words
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Rejects pictures with an excluded keyword in the title or description,
+/// keywords match whole words so `art` doesn't match `start`
+struct KeywordFilter {
+    /// Words of the keywords, a keyword of several words matches them in a row
+    keywords: Vec<Vec<String>>,
+}
+
+impl PictureFilter for KeywordFilter {
+    fn reject(&self, bytes: &[u8], metadata: &Metadata) -> Option<String> {
+        let _ = bytes;
+        let text = [&metadata.title, &metadata.description]
+            .into_iter()
+            .flatten()
+            .flat_map(|text| words(text))
+            .collect::<Vec<_>>();
+        self.keywords
+            .iter()
+            .find(|keyword| text.windows(keyword.len()).any(|words| words == *keyword))
+            .map(|keyword| format!("contains {}", keyword.join(" ")))
+    }
+}
+
+/// Rejects pictures looking like a recent wallpaper
+struct DuplicateFilter {
+    /// Perceptual hashes and titles of the recent wallpapers
+    shown: Vec<(u64, String)>,
+    /// Content hash of the current wallpaper, which is never rejected as setting it again
+    /// changes nothing, also when it was set before in the last days
+    current_hash: Option<String>,
+    threshold: u32,
+}
+
+impl DuplicateFilter {
+    /// Compares with the entries set since a time, all entries without one
+    fn new(
+        entries: Vec<HistoryEntry>,
+        current: Option<u64>,
+        since: Option<DateTime<Local>>,
+        threshold: u32,
+    ) -> Self {
+        let current_hash = entries
+            .iter()
+            .find(|entry| Some(entry.id) == current)
+            .and_then(|entry| entry.content_hash.clone());
+        let shown = entries
+            .into_iter()
+            .filter(|entry| {
+                since.is_none_or(|since| entry.timestamp >= since) && Some(entry.id) != current
+            })
+            .filter_map(|entry| {
+                let hash = u64::from_str_radix(entry.perceptual_hash.as_deref()?, 16).ok()?;
+                Some((hash, entry.title.unwrap_or_default()))
+            })
+            .collect();
+        DuplicateFilter {
+            shown,
+            current_hash,
+            threshold,
+        }
+    }
+}
+
+impl PictureFilter for DuplicateFilter {
+    fn reject(&self, bytes: &[u8], metadata: &Metadata) -> Option<String> {
+        if self.shown.is_empty() {
+            return None;
+        }
+        if let Some(current_hash) = &self.current_hash {
+            let hash = metadata
+                .content_hash
+                .clone()
+                .unwrap_or_else(|| content_hash(bytes));
+            if hash == *current_hash {
+                return None;
+            }
+        }
+        let hash = perceptual_hash(&image::load_from_memory(bytes).ok()?);
+        self.shown
+            .iter()
+            .find(|(shown, _)| (hash ^ shown).count_ones() <= self.threshold)
+            .map(|(_, title)| format!("looks like the recent wallpaper {}", title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageFormat;
+    use std::path::PathBuf;
+
+    fn metadata(title: &str, description: &str) -> Metadata {
+        Metadata {
+            title: Some(title.to_string()),
+            description: Some(description.to_string()),
+            source_url: None,
+            date: None,
+            content_hash: None,
+            provider: None,
+        }
+    }
+
+    /// Gradient encoded in a format, the same pixels have different bytes in other formats
+    fn encoded_picture(format: ImageFormat) -> Vec<u8> {
+        let image =
+            image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([x as u8 * 4, y as u8 * 5, 90]));
+        let mut bytes = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut bytes, format)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    fn history_entry(id: u64, bytes: &[u8]) -> HistoryEntry {
+        let image = image::load_from_memory(bytes).unwrap();
+        HistoryEntry {
+            id,
+            timestamp: Local::now(),
+            provider: "nasa".to_string(),
+            source_url: None,
+            title: Some(format!("Entry {}", id)),
+            description: None,
+            date: None,
+            content_hash: Some(content_hash(bytes)),
+            perceptual_hash: Some(format!("{:016x}", perceptual_hash(&image))),
+            path: PathBuf::from(format!("{}.jpg", id)),
+            original_path: None,
+            modifiers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn current_wallpaper_is_not_a_duplicate() {
+        let png = encoded_picture(ImageFormat::Png);
+        // the picture of the day set at login and again by a timer
+        let entries = vec![history_entry(1, &png), history_entry(2, &png)];
+        let filter = DuplicateFilter::new(entries, Some(2), None, DEFAULT_DEDUPE_THRESHOLD);
+        let mut candidate = metadata("Picture of the day", "");
+        candidate.content_hash = Some(content_hash(&png));
+        assert_eq!(filter.reject(&png, &candidate), None);
+
+        // a copy in another format still looks like the earlier entry
+        let bmp = encoded_picture(ImageFormat::Bmp);
+        candidate.content_hash = Some(content_hash(&bmp));
+        assert_eq!(
+            filter.reject(&bmp, &candidate),
+            Some("looks like the recent wallpaper Entry 1".to_string())
+        );
+    }
+
+    #[test]
+    fn keywords_match_whole_words() {
+        let filter = KeywordFilter {
+            keywords: ["Art", "map", "black hole"].map(words).to_vec(),
+        };
+        let reject = |title, description| filter.reject(&[], &metadata(title, description));
+        assert_eq!(reject("Mars particle start", "Mapped by a rover"), None);
+        assert_eq!(reject("Black holes", "A hole, black"), None);
+        assert_eq!(
+            reject("Street ART", "Sprayed on a wall"),
+            Some("contains art".to_string())
+        );
+        assert_eq!(
+            reject("M87", "The first image of a black-hole."),
+            Some("contains black hole".to_string())
+        );
+    }
+}
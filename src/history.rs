@@ -0,0 +1,408 @@
+use crate::config::{HistoryCommand, MarkArgs};
+use crate::error::PodError;
+use crate::filter;
+use crate::picture_io::Metadata;
+use crate::pipeline;
+
+use chrono::{DateTime, Local, NaiveDate};
+use image::DynamicImage;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_string_pretty};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE_NAME: &str = "history.json";
+const BANNED_FILE_NAME: &str = "banned.json";
+/// Wallpapers kept in the history, favorites and banned pictures are kept in addition
+const HISTORY_ENTRIES: usize = 100;
+
+/// An applied wallpaper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// When the wallpaper was set
+    pub timestamp: DateTime<Local>,
+    pub provider: String,
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Publication date of the picture
+    pub date: Option<NaiveDate>,
+    /// Sha-256 of the downloaded picture
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Perceptual hash of the picture before the modifiers, alike pictures have alike hashes
+    #[serde(default)]
+    pub perceptual_hash: Option<String>,
+    /// Copy of the wallpaper as it was set
+    pub path: PathBuf,
+    /// Copy of the picture before the modifiers, to apply other modifiers later
+    #[serde(default)]
+    pub original_path: Option<PathBuf>,
+    /// Modifiers applied to the picture, e.g. `fit 2560x1440`
+    pub modifiers: Vec<String>,
+}
+
+impl HistoryEntry {
+    /// Metadata of the picture as returned by the provider
+    pub fn get_metadata(&self) -> Metadata {
+        Metadata {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            source_url: self.source_url.clone(),
+            date: self.date,
+            content_hash: self.content_hash.clone(),
+            provider: Some(self.provider.clone()),
+        }
+    }
+
+    /// Whether the title, description, provider or source url contains the text, ignoring case
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        [
+            Some(&self.provider),
+            self.title.as_ref(),
+            self.description.as_ref(),
+            self.source_url.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| field.to_lowercase().contains(&text))
+    }
+}
+
+/// Picture skipped by all providers, matched by source url or content hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BannedPicture {
+    pub source_url: Option<String>,
+    pub content_hash: Option<String>,
+    /// Only for people reading the file
+    pub title: Option<String>,
+}
+
+impl BannedPicture {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        (self.content_hash.is_some() && self.content_hash == entry.content_hash)
+            || (self.source_url.is_some() && self.source_url == entry.source_url)
+    }
+}
+
+/// Applied wallpapers, kept in `history.json` with a copy of each wallpaper in `pictures`
+/// in the `pod` user data directory (`~/.local/share/pod` on Linux).
+/// Banned pictures are kept in `banned.json`, favorites are copied to `favorites`.
+pub struct History {
+    dir: PathBuf,
+}
+
+impl History {
+    pub fn open() -> Result<Self, PodError> {
+        let dir = dirs::data_dir()
+            .map(|dir| dir.join("pod"))
+            .ok_or_else(|| PodError::History("User data directory not found".to_string()))?;
+        Ok(History { dir })
+    }
+
+    /// Reads a json file of the history directory, a missing file is empty
+    fn read_json<T: DeserializeOwned + Default>(&self, file_name: &str) -> Result<T, PodError> {
+        let path = self.dir.join(file_name);
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))?;
+        from_str(&text).map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Replaces a json file of the history directory, written next to it first so a failed
+    /// write keeps the old one
+    fn write_json<T: Serialize + ?Sized>(
+        &self,
+        file_name: &str,
+        value: &T,
+    ) -> Result<(), PodError> {
+        let path = self.dir.join(file_name);
+        let temp_path = path.with_extension("json.tmp");
+        let json = to_string_pretty(value).map_err(|err| PodError::History(err.to_string()))?;
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&temp_path, json))
+            .and_then(|_| fs::rename(&temp_path, &path))
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Id of the wallpaper set last, the latest entry unless another one was applied since
+    pub fn current(&self) -> Result<Option<u64>, PodError> {
+        let current = fs::read_to_string(self.dir.join("current"))
+            .ok()
+            .and_then(|text| text.trim().parse().ok());
+        match current {
+            Some(id) => Ok(Some(id)),
+            None => Ok(self.entries()?.last().map(|entry| entry.id)),
+        }
+    }
+
+    pub fn set_current(&self, id: u64) -> Result<(), PodError> {
+        let path = self.dir.join("current");
+        fs::write(&path, id.to_string())
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>, PodError> {
+        self.read_json(HISTORY_FILE_NAME)
+    }
+
+    pub fn get(&self, id: u64) -> Result<HistoryEntry, PodError> {
+        self.entries()?
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| PodError::History(format!("No entry {}", id)))
+    }
+
+    /// Records a wallpaper as the current one, the saved picture is copied as it is overwritten
+    /// by the next run and the original is kept to apply other modifiers later.
+    /// Setting the current wallpaper again only updates the time of its entry.
+    pub fn add(
+        &self,
+        provider: String,
+        metadata: &Metadata,
+        original: &DynamicImage,
+        picture: &Path,
+        modifiers: Vec<String>,
+    ) -> Result<HistoryEntry, PodError> {
+        let mut entries = self.entries()?;
+        let current = self.current()?;
+        let same_as_current = entries.iter_mut().find(|entry| {
+            Some(entry.id) == current
+                && entry.content_hash.is_some()
+                && entry.content_hash == metadata.content_hash
+                && entry.modifiers == modifiers
+        });
+        if let Some(entry) = same_as_current {
+            entry.timestamp = Local::now();
+            let entry = entry.clone();
+            self.write_json(HISTORY_FILE_NAME, &entries)?;
+            return Ok(entry);
+        }
+        let id = entries.last().map_or(1, |entry| entry.id + 1);
+        let pictures_dir = self.dir.join("pictures");
+        fs::create_dir_all(&pictures_dir)
+            .map_err(|err| PodError::History(format!("{}: {}", pictures_dir.display(), err)))?;
+        let path = pictures_dir.join(format!("{}.jpg", id));
+        fs::copy(picture, &path)
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))?;
+        let original_path = pictures_dir.join(format!("{}.original.jpg", id));
+        pipeline::save(original, &original_path.to_string_lossy())?;
+
+        let entry = HistoryEntry {
+            id,
+            timestamp: Local::now(),
+            provider,
+            source_url: metadata.source_url.clone(),
+            title: metadata.title.clone(),
+            description: metadata.description.clone(),
+            date: metadata.date,
+            content_hash: metadata.content_hash.clone(),
+            perceptual_hash: Some(format!("{:016x}", filter::perceptual_hash(original))),
+            path,
+            original_path: Some(original_path),
+            modifiers,
+        };
+        entries.push(entry.clone());
+        let removed = self.prune(&mut entries)?;
+        self.write_json(HISTORY_FILE_NAME, &entries)?;
+        self.set_current(id)?;
+        // pictures last, they are still referenced when the history can't be written
+        for path in removed
+            .iter()
+            .flat_map(|entry| std::iter::once(&entry.path).chain(&entry.original_path))
+        {
+            if let Err(err) = fs::remove_file(path) {
+                eprintln!("Failed to remove {}: {}", path.display(), err);
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Takes the oldest entries beyond the kept ones out of the entries and returns them.
+    /// Favorites and banned pictures stay, so their marks can still be removed.
+    fn prune(&self, entries: &mut Vec<HistoryEntry>) -> Result<Vec<HistoryEntry>, PodError> {
+        let mut excess = entries.len().saturating_sub(HISTORY_ENTRIES);
+        if excess == 0 {
+            return Ok(Vec::new());
+        }
+        let banned = self.banned()?;
+        let favorites_dir = self.favorites_dir();
+        let (removed, kept) = entries.drain(..).partition(|entry| {
+            let marked = favorites_dir.join(format!("{}.jpg", entry.id)).exists()
+                || banned.iter().any(|banned| banned.matches(entry));
+            if excess > 0 && !marked {
+                excess -= 1;
+                true
+            } else {
+                false
+            }
+        });
+        *entries = kept;
+        Ok(removed)
+    }
+
+    pub fn banned(&self) -> Result<Vec<BannedPicture>, PodError> {
+        self.read_json(BANNED_FILE_NAME)
+    }
+
+    /// Bans the picture of an entry, returns false when it was banned already
+    pub fn ban(&self, entry: &HistoryEntry) -> Result<bool, PodError> {
+        if entry.source_url.is_none() && entry.content_hash.is_none() {
+            return Err(PodError::History(format!(
+                "Entry {} has neither a source url nor a content hash to ban",
+                entry.id
+            )));
+        }
+        let mut banned = self.banned()?;
+        if banned.iter().any(|banned| banned.matches(entry)) {
+            return Ok(false);
+        }
+        banned.push(BannedPicture {
+            source_url: entry.source_url.clone(),
+            content_hash: entry.content_hash.clone(),
+            title: entry.title.clone(),
+        });
+        self.write_json(BANNED_FILE_NAME, &banned)?;
+        Ok(true)
+    }
+
+    /// Lifts the ban of the picture of an entry, returns false when it wasn't banned
+    pub fn unban(&self, entry: &HistoryEntry) -> Result<bool, PodError> {
+        let mut banned = self.banned()?;
+        let count = banned.len();
+        banned.retain(|banned| !banned.matches(entry));
+        if banned.len() == count {
+            return Ok(false);
+        }
+        self.write_json(BANNED_FILE_NAME, &banned)?;
+        Ok(true)
+    }
+
+    /// Directory of the favorite pictures, each with a json sidecar read by the local provider
+    pub fn favorites_dir(&self) -> PathBuf {
+        self.dir.join("favorites")
+    }
+
+    /// Copies the original picture of an entry to the favorites, returns the copy
+    pub fn favorite(&self, entry: &HistoryEntry) -> Result<PathBuf, PodError> {
+        let dir = self.favorites_dir();
+        let path = dir.join(format!("{}.jpg", entry.id));
+        let source = entry.original_path.as_ref().unwrap_or(&entry.path);
+        let sidecar = json!({ "title": entry.title, "description": entry.description });
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::copy(source, &path))
+            .and_then(|_| fs::write(path.with_extension("json"), sidecar.to_string()))
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))?;
+        Ok(path)
+    }
+
+    /// Removes the picture of an entry from the favorites, returns false when it wasn't one
+    pub fn unfavorite(&self, entry: &HistoryEntry) -> Result<bool, PodError> {
+        let path = self.favorites_dir().join(format!("{}.jpg", entry.id));
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&path)
+            .and_then(|_| fs::remove_file(path.with_extension("json")))
+            .map_err(|err| PodError::History(format!("{}: {}", path.display(), err)))?;
+        Ok(true)
+    }
+}
+
+/// Marks the current wallpaper or a history entry as favorite or banned, or removes the mark
+pub fn mark(args: &MarkArgs, favorite: bool) -> Result<(), PodError> {
+    let history = History::open()?;
+    let id = match args.id {
+        Some(id) => id,
+        None => history
+            .current()?
+            .ok_or_else(|| PodError::History("No wallpaper set yet".to_string()))?,
+    };
+    let entry = history.get(id)?;
+    let title = entry.title.as_deref().unwrap_or("-");
+    match (favorite, args.remove) {
+        (true, false) => {
+            let path = history.favorite(&entry)?;
+            println!("Added {} to favorites as {}", title, path.display());
+        }
+        (true, true) => match history.unfavorite(&entry)? {
+            true => println!("Removed {} from favorites", title),
+            false => println!("{} is not a favorite", title),
+        },
+        (false, false) => match history.ban(&entry)? {
+            true => println!("Banned {}, it is skipped from now on", title),
+            false => println!("{} is banned already", title),
+        },
+        (false, true) => match history.unban(&entry)? {
+            true => println!("Lifted the ban of {}", title),
+            false => println!("{} is not banned", title),
+        },
+    }
+    Ok(())
+}
+
+/// Runs a `pod history` command
+pub fn run(command: &HistoryCommand) -> Result<(), PodError> {
+    let history = History::open()?;
+    match command {
+        HistoryCommand::List { limit, date } => {
+            let entries = history.entries()?;
+            let entries = entries
+                .iter()
+                .rev()
+                .filter(|entry| date.is_none_or(|date| entry.timestamp.date_naive() == date))
+                .take(*limit);
+            for entry in entries {
+                print_summary(entry);
+            }
+        }
+        HistoryCommand::Search { text, limit } => {
+            let entries = history.entries()?;
+            let entries = entries
+                .iter()
+                .rev()
+                .filter(|entry| entry.matches(text))
+                .take(*limit);
+            for entry in entries {
+                print_summary(entry);
+            }
+        }
+        HistoryCommand::Show { id } => {
+            let entry = history.get(*id)?;
+            let or_none = |value: &Option<String>| value.clone().unwrap_or("-".to_string());
+            println!("Id:          {}", entry.id);
+            println!(
+                "Set at:      {}",
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S")
+            );
+            println!("Provider:    {}", entry.provider);
+            println!(
+                "Published:   {}",
+                entry.date.map_or("-".to_string(), |date| date.to_string())
+            );
+            println!("Title:       {}", or_none(&entry.title));
+            println!("Source:      {}", or_none(&entry.source_url));
+            println!("Picture:     {}", entry.path.display());
+            let modifiers = Some(entry.modifiers.join(", ")).filter(|m| !m.is_empty());
+            println!("Modifiers:   {}", or_none(&modifiers));
+            println!("Description: {}", or_none(&entry.description));
+        }
+    }
+    Ok(())
+}
+
+fn print_summary(entry: &HistoryEntry) {
+    println!(
+        "{:>5}  {}  {:<10}  {}",
+        entry.id,
+        entry.timestamp.format("%Y-%m-%d %H:%M"),
+        entry.provider,
+        entry.title.as_deref().unwrap_or("-")
+    );
+}
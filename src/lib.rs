@@ -0,0 +1,29 @@
+//! Fetches a picture of the day, modifies it and sets it as wallpaper.
+//!
+//! The `pod` binary is a thin client of this library, other programs can use the providers,
+//! modifiers and the pipeline directly:
+//!
+//! ```no_run
+//! use clap::Parser;
+//! use pod::config::Config;
+//!
+//! let config = Config::parse_from(["pod", "--add-metadata", "true", "bing"]);
+//! let (mut image, metadata) = pod::pipeline::fetch(&config)?;
+//! pod::pipeline::apply_modifiers(&config, &mut image, metadata)?;
+//! pod::pipeline::save(&image, "/tmp/bing.jpg")?;
+//! # Ok::<(), pod::error::PodError>(())
+//! ```
+
+pub mod cache;
+pub mod config;
+pub mod config_file;
+pub mod daemon;
+pub mod error;
+pub mod filter;
+pub mod history;
+pub mod modifiers;
+pub mod picture_io;
+pub mod pipeline;
+pub mod rollback;
+pub mod secret;
+pub mod timer;
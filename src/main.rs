@@ -1,45 +1,38 @@
-mod config;
-mod picture_io;
-mod modifiers;
-
-use clap::Parser;
-use config::Config;
-use modifiers::{metadata_modifier::MetaDataModifier, size_modifier::SizeModifier, ImageModifier};
+use pod::config::{Command, Config};
+use pod::daemon;
+use pod::error::PodError;
+use pod::history;
+use pod::pipeline;
+use pod::rollback;
+use pod::timer;
 
 fn main() {
-    // parse command line arguments
-    let config = Config::parse();
-    println!("{:?}", config);
-
-    let (buffer, metadata) = picture_io::get_picture_of_day_with_metadata(&config).expect("Failed to fetch image");
-    println!("Hacking complete");
-    let mut image = image::load_from_memory(&buffer).expect("Failed to load image");
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    }
+}
 
-    println!("Applying modifiers");
-    // apply size_modifier first
-    if config.fit_to_screen_size.unwrap_or(false) {
-        // get screen size
-        // TODO from actual screen?
-        let w = config.width.expect("need a target width");
-        let h = config.height.expect("need a target height");
-        let size_modifier = SizeModifier::new(w, h);
-        let result = size_modifier.modify(&mut image);
-        if result.is_err() {
-            println!("Failed to resize image");
-        }
+fn run() -> Result<(), PodError> {
+    // parse command line arguments and the config file
+    let config = Config::load()?;
+    // keep the output of history listings clean
+    if !matches!(config.command, Some(Command::History(_))) {
+        println!("{:?}", config);
     }
-    // apply metadata_modifier next
-    if config.add_metadata.unwrap_or(false) {
-        let metadata_modifier = MetaDataModifier::new(metadata, &config);
-        let result = metadata_modifier.modify(&mut image);
-        if result.is_err() {
-            println!("Failed to add metadata");
+
+    match &config.command {
+        Some(Command::Daemon(args)) => daemon::run(&config, args),
+        Some(Command::InstallTimer(args)) => {
+            timer::install(args, &std::env::args().collect::<Vec<_>>()).map(|_| ())
         }
+        Some(Command::UninstallTimer(args)) => timer::uninstall(args).map(|_| ()),
+        Some(Command::History(command)) => history::run(command),
+        Some(Command::Previous(args)) => rollback::step(&config, -1, args),
+        Some(Command::Next(args)) => rollback::step(&config, 1, args),
+        Some(Command::Apply { id, args }) => rollback::apply(&config, *id, args),
+        Some(Command::Favorite(args)) => history::mark(args, true),
+        Some(Command::Ban(args)) => history::mark(args, false),
+        _ => pipeline::run(&config).map(|_| ()),
     }
-    // save and set background
-    let path = config.get_picture_file_name();
-    println!("Saving picture to: {}", path);
-    image.save_with_format(&path, image::ImageFormat::Jpeg).expect("Failed to save image");
-    println!("Setting wallpaper");
-    wallpaper::set_from_path(&path).expect("Failed to set wallpaper");
 }
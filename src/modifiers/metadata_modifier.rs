@@ -80,6 +80,13 @@ impl<'a> ImageModifier for MetaDataModifier<'a> {
         let height = line_height * buffer.layout_runs().count() as f32;
         let x_offset = image.width() as f32 - width - 10.0;
         let y_offset: f32 = 10.0;
+        if x_offset < 0.0 || y_offset + height > image.height() as f32 {
+            return Err(ImageModifierError::ImageProcessingError(format!(
+                "image {}x{} is too small for the metadata text",
+                image.width(),
+                image.height()
+            )));
+        }
 
         // calculate average luma of the area where the text will be drawn
         let mut avg_luma: f32 = 0.0;
@@ -1,22 +1,34 @@
+use crate::config::FitMode;
 use crate::modifiers::{ImageModifier, ImageModifierError};
-use image::DynamicImage;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Longest side of the copy the edge energy of smart fill is estimated on
+const ENERGY_SIZE: u32 = 256;
 
 /// A modifier that resizes an image to the specified dimensions.
 ///
 /// # Fields:
 /// - `target_width`: The desired width of the image in pixels.
 /// - `target_height`: The desired height of the image in pixels.
+/// - `fit_mode`: How the image is fitted to the dimensions, fill by default.
 ///
 /// This modifier validates the target dimensions and resizes the image using
 /// a high-quality Lanczos3 filter.
 pub struct SizeModifier {
     width: u32,
     height: u32,
+    fit_mode: FitMode,
 }
 
 impl SizeModifier {
     pub fn new(width: u32, height: u32) -> Self {
-        SizeModifier { width, height }
+        SizeModifier { width, height, fit_mode: FitMode::Fill }
+    }
+
+    pub fn with_fit_mode(mut self, fit_mode: FitMode) -> Self {
+        self.fit_mode = fit_mode;
+        self
     }
 
     fn validate_dimensions(&self) -> Result<(), ImageModifierError> {
@@ -28,6 +40,84 @@ impl SizeModifier {
             Ok(())
         }
     }
+
+    /// Black canvas of the target size
+    fn canvas(&self) -> RgbaImage {
+        RgbaImage::from_pixel(self.width, self.height, Rgba([0, 0, 0, 255]))
+    }
+
+    /// Draws the image centered on the background, cropping what doesn't fit
+    fn center_on(&self, mut background: RgbaImage, image: &DynamicImage) -> DynamicImage {
+        let x = (i64::from(self.width) - i64::from(image.width())) / 2;
+        let y = (i64::from(self.height) - i64::from(image.height())) / 2;
+        imageops::overlay(&mut background, &image.to_rgba8(), x, y);
+        DynamicImage::ImageRgba8(background)
+    }
+
+    /// Crop window of the target aspect ratio with the most edge energy, as x, y, width, height.
+    /// Energy is estimated on a small gray copy, the window only slides along the cropped axis.
+    fn smart_crop_window(&self, image: &DynamicImage) -> (u32, u32, u32, u32) {
+        let (width, height) = (image.width(), image.height());
+        let scale = f64::max(
+            f64::from(self.width) / f64::from(width),
+            f64::from(self.height) / f64::from(height),
+        );
+        let crop_width = ((f64::from(self.width) / scale).round() as u32).clamp(1, width);
+        let crop_height = ((f64::from(self.height) / scale).round() as u32).clamp(1, height);
+        let horizontal = crop_width < width;
+        if !horizontal && crop_height == height {
+            return (0, 0, width, height);
+        }
+
+        let small = image.thumbnail(ENERGY_SIZE, ENERGY_SIZE).to_luma8();
+        let (small_width, small_height) = small.dimensions();
+        // gradient magnitude summed per column or row
+        let mut energy = vec![0u64; if horizontal { small_width } else { small_height } as usize];
+        for y in 0..small_height.saturating_sub(1) {
+            for x in 0..small_width.saturating_sub(1) {
+                let value = i32::from(small.get_pixel(x, y)[0]);
+                let dx = (value - i32::from(small.get_pixel(x + 1, y)[0])).unsigned_abs();
+                let dy = (value - i32::from(small.get_pixel(x, y + 1)[0])).unsigned_abs();
+                let index = if horizontal { x } else { y };
+                energy[index as usize] += u64::from(dx + dy);
+            }
+        }
+
+        // slide the window over the small copy, of the windows with about the most energy
+        // the middle one is taken so a subject smaller than the window ends up centered
+        let (full, crop) = if horizontal {
+            (width, crop_width)
+        } else {
+            (height, crop_height)
+        };
+        let window = ((energy.len() as f64 * f64::from(crop) / f64::from(full)).round() as usize)
+            .clamp(1, energy.len());
+        let sums: Vec<u64> = energy.windows(window).map(|w| w.iter().sum()).collect();
+        let max = sums.iter().copied().max().unwrap_or(0);
+        let candidates: Vec<usize> = (0..sums.len())
+            .filter(|&start| sums[start] * 50 >= max * 49)
+            .collect();
+        let best = candidates[candidates.len() / 2];
+        let offset = ((best as f64 * f64::from(full) / energy.len() as f64).round() as u32)
+            .min(full - crop);
+        if horizontal {
+            (offset, 0, crop_width, crop_height)
+        } else {
+            (0, offset, crop_width, crop_height)
+        }
+    }
+
+    /// Blurred copy of the image covering the target size, blurred small as blurring is slow
+    fn blurred_background(&self, image: &DynamicImage) -> RgbaImage {
+        let small = image
+            .resize_to_fill(
+                (self.width / 16).max(1),
+                (self.height / 16).max(1),
+                FilterType::Triangle,
+            )
+            .blur(2.0);
+        imageops::resize(&small.to_rgba8(), self.width, self.height, FilterType::Triangle)
+    }
 }
 
 impl ImageModifier for SizeModifier {
@@ -35,19 +125,98 @@ impl ImageModifier for SizeModifier {
         // Validate dimensions
         self.validate_dimensions()?;
         println!(
-            "Resizing to {}-{} from {}-{}",
+            "Resizing to {}-{} from {}-{} ({})",
             self.width,
             self.height,
             image.width(),
-            image.height()
-        );
-        
-        *image = image.resize_to_fill(
-            self.width,
-            self.height,
-            image::imageops::FilterType::Lanczos3,
+            image.height(),
+            self.fit_mode
         );
+
+        *image = match self.fit_mode {
+            FitMode::Fill => image.resize_to_fill(self.width, self.height, FilterType::Lanczos3),
+            FitMode::SmartFill => {
+                let (x, y, width, height) = self.smart_crop_window(image);
+                println!("Cropping {}-{} at {}-{}", width, height, x, y);
+                image.crop_imm(x, y, width, height).resize_exact(
+                    self.width,
+                    self.height,
+                    FilterType::Lanczos3,
+                )
+            }
+            FitMode::Contain => {
+                let resized = image.resize(self.width, self.height, FilterType::Lanczos3);
+                self.center_on(self.canvas(), &resized)
+            }
+            FitMode::ContainBlur => {
+                let resized = image.resize(self.width, self.height, FilterType::Lanczos3);
+                self.center_on(self.blurred_background(image), &resized)
+            }
+            FitMode::Stretch => image.resize_exact(self.width, self.height, FilterType::Lanczos3),
+            FitMode::Center => self.center_on(self.canvas(), image),
+            FitMode::Tile => {
+                let mut canvas = self.canvas();
+                imageops::tile(&mut canvas, &image.to_rgba8());
+                DynamicImage::ImageRgba8(canvas)
+            }
+        };
         println!("Resized to {}-{}", image.width(), image.height());
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// Gray picture with a detailed checkerboard patch
+    fn picture(width: u32, height: u32, patch: (u32, u32, u32, u32)) -> DynamicImage {
+        let (patch_x, patch_y, patch_width, patch_height) = patch;
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+            let in_patch = (patch_x..patch_x + patch_width).contains(&x)
+                && (patch_y..patch_y + patch_height).contains(&y);
+            if in_patch && (x / 10 + y / 10) % 2 == 0 {
+                Luma([255])
+            } else {
+                Luma([40])
+            }
+        }))
+    }
+
+    fn smart_fill(width: u32, height: u32) -> SizeModifier {
+        SizeModifier::new(width, height).with_fit_mode(FitMode::SmartFill)
+    }
+
+    #[test]
+    fn smart_crop_keeps_subject_near_edge() {
+        let image = picture(1200, 400, (20, 100, 200, 200));
+        let (x, y, width, height) = smart_fill(400, 400).smart_crop_window(&image);
+        assert_eq!((y, width, height), (0, 400, 400));
+        assert!(x <= 20 && x + width >= 220, "window starts at {}", x);
+
+        let image = picture(400, 1200, (100, 1000, 200, 180));
+        let (x, y, width, height) = smart_fill(800, 800).smart_crop_window(&image);
+        assert_eq!((x, width, height), (0, 400, 400));
+        assert!(y <= 1000 && y + height >= 1180, "window starts at {}", y);
+    }
+
+    #[test]
+    fn smart_crop_centers_uniform_picture() {
+        let image = picture(1200, 400, (0, 0, 0, 0));
+        let (x, y, width, height) = smart_fill(1920, 1920).smart_crop_window(&image);
+        assert_eq!((y, width, height), (0, 400, 400));
+        // within a pixel of the small copy the energy is estimated on
+        let pixel = 1200 / ENERGY_SIZE;
+        assert!(x.abs_diff(400) <= pixel, "window starts at {}", x);
+    }
+
+    #[test]
+    fn smart_crop_keeps_matching_aspect_ratio() {
+        let image = picture(1600, 900, (0, 0, 100, 100));
+        assert_eq!(
+            smart_fill(1920, 1080).smart_crop_window(&image),
+            (0, 0, 1600, 900)
+        );
+    }
+}
@@ -1,28 +1,262 @@
 pub mod picture_bing_provider;
+pub mod picture_exec_provider;
+pub mod picture_fallback_provider;
+pub mod picture_feed_provider;
+pub mod picture_json_provider;
+pub mod picture_local_provider;
 pub mod picture_nasa_provider;
+pub mod picture_random_provider;
+pub mod picture_wikimedia_provider;
 
+use crate::cache::{Cache, CacheKey};
 use crate::config::{Config, Provider};
+use crate::error::PodError;
+use crate::filter;
+use crate::history::History;
+use crate::secret::Secret;
+use picture_nasa_provider::NasaQuery;
 
-#[derive(Debug)]
+use chrono::NaiveDate;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::{IntoUrl, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
     pub title: Option<String>,
     pub description: Option<String>,
+    /// Where the picture came from, e.g. the image url or its page
+    pub source_url: Option<String>,
+    /// Publication date, for providers with a picture per day
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    /// Sha-256 of the picture, set after the download
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Name of the provider that returned the picture, e.g. the provider picked by random
+    #[serde(default)]
+    pub provider: Option<String>,
 }
 
+/// Fetches of a provider returning rejected pictures
+const MAX_FETCH_ATTEMPTS: usize = 5;
+
 pub trait PictureProvider {
     /// Fetches picture data and metadata
-    fn get_picture_with_metadata(&self, config: &Config) -> std::io::Result<(Vec<u8>, Metadata)>;
+    fn get_picture_with_metadata(&self, config: &Config) -> Result<(Vec<u8>, Metadata), PodError>;
+
+    /// Key of the picture the next fetch returns, only providers with a picture per day have one.
+    /// Pictures with a key are cached.
+    fn cache_key(&self, config: &Config) -> Option<CacheKey> {
+        let _ = config;
+        None
+    }
+}
+
+/// Creates the provider implementation for a provider configuration
+pub fn get_provider(provider: &Provider) -> Result<Box<dyn PictureProvider>, PodError> {
+    let picture_provider: Box<dyn PictureProvider> = match provider {
+        Provider::Nasa {
+            nasa_random,
+            nasa_api_key,
+            nasa_api_key_file,
+            date,
+            start_date,
+            end_date,
+            video_thumbnail,
+        } => {
+            let query = match (nasa_random, date, start_date) {
+                (true, _, _) => NasaQuery::Random,
+                (false, Some(date), _) => NasaQuery::Date(*date),
+                (false, None, Some(start_date)) => NasaQuery::Range(*start_date, *end_date),
+                (false, None, None) => NasaQuery::Today,
+            };
+            let api_key = Secret::resolve(
+                "NASA api key (--nasa-api-key, POD_NASA_API_KEY or --nasa-api-key-file)",
+                nasa_api_key,
+                nasa_api_key_file,
+            )?;
+            Box::new(picture_nasa_provider::PictureNasaProvider::new(
+                api_key,
+                query,
+                *video_thumbnail,
+            ))
+        }
+        Provider::Bing {
+            market,
+            idx,
+            count,
+            resolution,
+        } => Box::new(picture_bing_provider::PictureBingProvider::new(
+            market.clone(),
+            *idx,
+            *count,
+            *resolution,
+        )),
+        Provider::Wikimedia { wikimedia_api_url } => Box::new(
+            picture_wikimedia_provider::PictureWikimediaProvider::new(wikimedia_api_url.clone()),
+        ),
+        Provider::Local { dir, order } => Box::new(
+            picture_local_provider::PictureLocalProvider::new(dir.clone(), *order),
+        ),
+        Provider::Favorites { order } => {
+            Box::new(picture_local_provider::PictureLocalProvider::new(
+                History::open()?.favorites_dir(),
+                *order,
+            ))
+        }
+        Provider::Json {
+            url,
+            image_path,
+            title_path,
+            description_path,
+        } => Box::new(picture_json_provider::PictureJsonProvider::new(
+            url.clone(),
+            image_path.clone(),
+            title_path.clone(),
+            description_path.clone(),
+        )),
+        Provider::Feed { url, pick } => Box::new(picture_feed_provider::PictureFeedProvider::new(
+            url.clone(),
+            *pick,
+        )),
+        Provider::Exec {
+            timeout,
+            command,
+            args,
+        } => Box::new(picture_exec_provider::PictureExecProvider::new(
+            command.clone(),
+            args.clone(),
+            Duration::from_secs(*timeout),
+        )),
+        Provider::Fallback { providers } => Box::new(
+            picture_fallback_provider::PictureFallbackProvider::new(providers.clone()),
+        ),
+        Provider::Random { providers } => Box::new(
+            picture_random_provider::PictureRandomProvider::new(providers.clone()),
+        ),
+    };
+    Ok(picture_provider)
 }
 
-fn get_provider(config: &Config) -> Box<dyn PictureProvider> {
-    match config.provider {
-        Provider::Nasa { .. } => Box::new(picture_nasa_provider::PictureNasaProvider::new()),
-        Provider::Bing => Box::new(picture_bing_provider::PictureBingProvider::new()),
+/// Sends a GET request, failed requests and unsuccessful statuses are returned as errors
+fn http_get<U: IntoUrl>(url: U) -> Result<Response, PodError> {
+    send(reqwest::blocking::Client::new().get(url))
+}
+
+/// Sends a request, failed requests and unsuccessful statuses are returned as errors
+fn send(request: RequestBuilder) -> Result<Response, PodError> {
+    let response = request.send().map_err(|mut err| {
+        if let Some(url) = err.url_mut() {
+            redact_url(url);
+        }
+        PodError::Network(err.to_string())
+    })?;
+    if !response.status().is_success() {
+        let mut url = response.url().clone();
+        redact_url(&mut url);
+        return Err(PodError::HttpStatus {
+            url: url.to_string(),
+            status: response.status().as_u16(),
+        });
+    }
+    Ok(response)
+}
+
+/// Hides api keys and tokens in query parameters so errors can be logged
+fn redact_url(url: &mut Url) {
+    let is_secret = |name: &str| matches!(name, "api_key" | "key" | "token" | "access_token");
+    if !url.query_pairs().any(|(name, _)| is_secret(&name)) {
+        return;
     }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_secret(&name) {
+                "***".to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
 }
 
-/// Fetches picture data and metadata
-pub fn get_picture_of_day_with_metadata(config: &Config) -> std::io::Result<(Vec<u8>, Metadata)> {
-    let provider = get_provider(config);
-    provider.get_picture_with_metadata(config)
+/// Reads the whole response body, e.g. a downloaded picture
+fn read_bytes(response: Response) -> Result<Vec<u8>, PodError> {
+    response
+        .bytes()
+        .map(|bytes| bytes.to_vec())
+        .map_err(|err| PodError::Network(err.to_string()))
+}
+
+/// Reads the whole response body as text, e.g. a json document
+fn read_text(response: Response) -> Result<String, PodError> {
+    response
+        .text()
+        .map_err(|err| PodError::Network(err.to_string()))
+}
+
+/// Fetches picture data and metadata of the configured provider
+pub fn get_picture_of_day_with_metadata(config: &Config) -> Result<(Vec<u8>, Metadata), PodError> {
+    fetch(config.get_provider()?, config)
+}
+
+/// Fetches a picture of a provider that passes the filters, providers wrapping other providers
+/// fetch through this as well so their pictures are cached and filtered.
+/// Rejected pictures are fetched again until the provider returns the same picture twice.
+fn fetch(provider_config: &Provider, config: &Config) -> Result<(Vec<u8>, Metadata), PodError> {
+    let provider = get_provider(provider_config)?;
+    let filters = filter::get_filters(config)?;
+    let mut last_hash = None;
+    for _ in 0..MAX_FETCH_ATTEMPTS {
+        let (bytes, metadata) = fetch_cached(provider_config, provider.as_ref(), config)?;
+        let Some(reason) = filter::reject(&filters, &bytes, &metadata) else {
+            return Ok((bytes, metadata));
+        };
+        println!(
+            "Skipping {}: {}",
+            metadata.title.as_deref().unwrap_or("picture"),
+            reason
+        );
+        if last_hash.is_some() && last_hash == metadata.content_hash {
+            break;
+        }
+        last_hash = metadata.content_hash;
+    }
+    Err(PodError::Provider(format!(
+        "{} returned no acceptable picture",
+        provider_config
+    )))
+}
+
+/// Fetches picture data and metadata, from the cache when the picture was fetched before
+fn fetch_cached(
+    provider_config: &Provider,
+    provider: &dyn PictureProvider,
+    config: &Config,
+) -> Result<(Vec<u8>, Metadata), PodError> {
+    let cache = Cache::new(provider_config).filter(|_| !config.no_cache);
+    let key = cache.as_ref().and_then(|_| provider.cache_key(config));
+    if let (Some(cache), Some(key)) = (&cache, &key) {
+        if let Some(picture) = cache.load(key) {
+            return Ok(picture);
+        }
+    }
+
+    let (bytes, mut metadata) = provider.get_picture_with_metadata(config)?;
+    metadata.content_hash = Some(filter::content_hash(&bytes));
+    // providers wrapping other providers keep the name of the wrapped one
+    metadata
+        .provider
+        .get_or_insert_with(|| provider_config.to_string());
+    if let (Some(cache), Some(key)) = (&cache, key) {
+        // a missing cache entry only costs a download next time
+        if let Err(err) = cache.store(&key, &bytes, &metadata) {
+            eprintln!("Failed to cache picture: {}", err);
+        }
+    }
+    Ok((bytes, metadata))
 }
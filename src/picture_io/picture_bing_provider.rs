@@ -1,62 +1,144 @@
-use crate::config::Config;
+use crate::cache::CacheKey;
+use crate::config::{BingResolution, Config};
+use crate::error::PodError;
 use crate::picture_io::PictureProvider;
 
-use reqwest;
+use chrono::{Local, NaiveDate, TimeDelta};
+use rand::seq::IndexedRandom;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::from_str;
 use serde_json::Value;
+use std::cell::OnceCell;
 
-pub struct PictureBingProvider;
+pub struct PictureBingProvider {
+    market: String,
+    idx: u8,
+    count: u8,
+    resolution: BingResolution,
+    /// Archive response, requested once for the cache key and the picture
+    response: OnceCell<BingResponse>,
+}
 
 impl PictureBingProvider {
-    pub fn new() -> Self {
-        PictureBingProvider
+    pub fn new(market: String, idx: u8, count: u8, resolution: BingResolution) -> Self {
+        PictureBingProvider {
+            market,
+            idx,
+            count,
+            resolution,
+            response: OnceCell::new(),
+        }
+    }
+
+    /// Archive of the days starting at idx, only requested once
+    fn get_response(&self) -> Result<&BingResponse, PodError> {
+        if let Some(response) = self.response.get() {
+            return Ok(response);
+        }
+        println!("Hacking Bing server...");
+        let response = super::http_get(self.get_url())?;
+        let response_text = super::read_text(response)?;
+        let bing_response = from_str::<BingResponse>(&response_text)
+            .map_err(|err| PodError::Parse(format!("bing response: {}", err)))?;
+        Ok(self.response.get_or_init(|| bing_response))
+    }
+
+    /// Resolves auto to the smallest resolution of the same orientation covering the target size,
+    /// UHD for larger landscape targets
+    fn get_resolution(&self, config: &Config) -> BingResolution {
+        if self.resolution != BingResolution::Auto {
+            return self.resolution;
+        }
+        let (width, height) = match (config.width, config.height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return BingResolution::R1920x1080,
+        };
+        let portrait = height > width;
+        BingResolution::SIZES
+            .into_iter()
+            .filter_map(|resolution| resolution.size().map(|size| (resolution, size)))
+            .filter(|(_, (w, h))| (h > w) == portrait)
+            .find(|(_, (w, h))| *w >= width && *h >= height)
+            .map(|(resolution, _)| resolution)
+            .unwrap_or(if portrait {
+                BingResolution::R1080x1920
+            } else {
+                BingResolution::Uhd
+            })
+    }
+
+    fn get_url(&self) -> String {
+        format!(
+            "https://www.bing.com/HPImageArchive.aspx?format=js&idx={}&n={}&mkt={}",
+            self.idx, self.count, self.market
+        )
     }
 }
 
 impl PictureProvider for PictureBingProvider {
+    /// Single days are cached per market and resolution, random picks of several days are not.
+    /// The day is the start date of the market's picture, which changes at midnight in the market,
+    /// so only the small archive response is downloaded again. Without a response the local day is used.
+    fn cache_key(&self, config: &Config) -> Option<CacheKey> {
+        if self.count != 1 {
+            return None;
+        }
+        let market_date = self.get_response().ok().and_then(|response| {
+            let image = response.images.first()?;
+            NaiveDate::parse_from_str(&image.startdate, "%Y%m%d").ok()
+        });
+        let date = market_date
+            .unwrap_or_else(|| Local::now().date_naive() - TimeDelta::days(self.idx.into()));
+        let variant = format!("{}_{}", self.market, self.get_resolution(config).suffix());
+        Some(CacheKey::new(date, &variant))
+    }
+
     fn get_picture_with_metadata(
         &self,
         config: &Config,
-    ) -> std::io::Result<(Vec<u8>, super::Metadata)> {
-        println!("Hacking Bing server...");
-        let _ = config;
-        let url = "https://www.bing.com/HPImageArchive.aspx?format=js&idx=0&n=1&mkt=en-US";
-        // fetch data from NASA API
-        let response = reqwest::blocking::get(url).expect("Failed to send request");
-        // if response is not succesful print error message and exit
-        if !response.status().is_success() {
-            eprintln!("Failed to fetch data from bing");
-            std::process::exit(1);
-        }
-
-        // parse response as json
-        let response_text = response.text().unwrap();
-        let bing_response =
-            from_str::<BingResponse>(&response_text).expect("Failed to parse media type");
-        let image = &bing_response.images[0];
-        let image_url = format!("https://www.bing.com{}", image.url);
-        let image_response = reqwest::blocking::get(&image_url).expect("Failed to send request");
-        let bytes = image_response.bytes().expect("Failed to read image bytes");
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        // with a count of more than one a random day is picked to catch up on missed days
+        let image = self
+            .get_response()?
+            .images
+            .choose(&mut rand::rng())
+            .ok_or_else(|| PodError::Provider("Bing returned no images".to_string()))?;
+        let resolution = self.get_resolution(config);
+        println!(
+            "Picked picture of {} in {}",
+            image.startdate,
+            resolution.suffix()
+        );
+        let image_url = format!(
+            "https://www.bing.com{}{}",
+            image.urlbase,
+            resolution.suffix()
+        );
+        let image_response = super::http_get(&image_url)?;
+        let bytes = super::read_bytes(image_response)?;
         let metadata = super::Metadata {
             title: Some(image.title.clone()),
             description: Some(image.copyright.clone()),
+            source_url: Some(image_url),
+            date: NaiveDate::parse_from_str(&image.startdate, "%Y%m%d").ok(),
+            content_hash: None,
+            provider: None,
         };
         // return image bytes and metadata
-        Ok((bytes.to_vec(), metadata))
+        Ok((bytes, metadata))
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct BingResponse {
     pub images: Vec<Image>,
     pub tooltips: Tooltips,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct Image {
     pub startdate: String,
     pub fullstartdate: String,
@@ -76,7 +158,7 @@ pub struct Image {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct Tooltips {
     pub loading: String,
     pub previous: String,
@@ -84,3 +166,78 @@ pub struct Tooltips {
     pub walle: String,
     pub walls: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn resolution(resolution: BingResolution, size: Option<(u32, u32)>) -> BingResolution {
+        let mut args = vec!["pod".to_string()];
+        if let Some((width, height)) = size {
+            args.extend(["--width".to_string(), width.to_string()]);
+            args.extend(["--height".to_string(), height.to_string()]);
+        }
+        args.push("bing".to_string());
+        let config = Config::try_parse_from(args).unwrap();
+        PictureBingProvider::new("en-US".to_string(), 0, 1, resolution).get_resolution(&config)
+    }
+
+    #[test]
+    fn auto_resolution_covers_screen() {
+        let auto = |size| resolution(BingResolution::Auto, size);
+        assert_eq!(auto(None), BingResolution::R1920x1080);
+        assert_eq!(auto(Some((1280, 720))), BingResolution::R1280x768);
+        assert_eq!(auto(Some((1366, 768))), BingResolution::R1366x768);
+        assert_eq!(auto(Some((1920, 1080))), BingResolution::R1920x1080);
+        assert_eq!(auto(Some((1920, 1200))), BingResolution::R1920x1200);
+        assert_eq!(auto(Some((2560, 1440))), BingResolution::Uhd);
+        assert_eq!(auto(Some((3840, 2160))), BingResolution::Uhd);
+    }
+
+    #[test]
+    fn auto_resolution_keeps_portrait() {
+        let auto = |size| resolution(BingResolution::Auto, size);
+        assert_eq!(auto(Some((720, 1280))), BingResolution::R768x1280);
+        assert_eq!(auto(Some((1080, 1920))), BingResolution::R1080x1920);
+        // no portrait picture is larger
+        assert_eq!(auto(Some((1440, 2560))), BingResolution::R1080x1920);
+    }
+
+    #[test]
+    fn cache_key_uses_market_day() {
+        let config = Config::try_parse_from(["pod", "bing"]).unwrap();
+        let provider =
+            PictureBingProvider::new("ja-JP".to_string(), 0, 1, BingResolution::R1920x1080);
+        let image = Image {
+            startdate: "20250214".to_string(),
+            ..Image::default()
+        };
+        let _ = provider.response.set(BingResponse {
+            images: vec![image],
+            ..BingResponse::default()
+        });
+        assert_eq!(
+            provider.cache_key(&config),
+            Some(CacheKey::new(
+                NaiveDate::from_ymd_opt(2025, 2, 14).unwrap(),
+                "ja-JP__1920x1080.jpg"
+            ))
+        );
+
+        let several_days =
+            PictureBingProvider::new("ja-JP".to_string(), 0, 2, BingResolution::R1920x1080);
+        assert_eq!(several_days.cache_key(&config), None);
+    }
+
+    #[test]
+    fn fixed_resolution_is_kept() {
+        assert_eq!(
+            resolution(BingResolution::R1024x768, Some((3840, 2160))),
+            BingResolution::R1024x768
+        );
+        assert_eq!(resolution(BingResolution::Uhd, None), BingResolution::Uhd);
+        assert_eq!(BingResolution::Uhd.suffix(), "_UHD.jpg");
+        assert_eq!(BingResolution::R768x1280.suffix(), "_768x1280.jpg");
+    }
+}
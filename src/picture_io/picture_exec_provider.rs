@@ -0,0 +1,139 @@
+use crate::config::Config;
+use crate::error::PodError;
+use crate::picture_io::PictureProvider;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_slice;
+use std::fs;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often a running command is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Provider implemented by an external executable.
+///
+/// The command prints a json document to stdout:
+/// `{"image": "<path or url>", "title": "...", "description": "...", "link": "..."}`.
+/// Only `image` is required. Commands running longer than the timeout are killed.
+pub struct PictureExecProvider {
+    command: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl PictureExecProvider {
+    pub fn new(command: String, args: Vec<String>, timeout: Duration) -> Self {
+        PictureExecProvider {
+            command,
+            args,
+            timeout,
+        }
+    }
+
+    /// Runs the command, returns its stdout or the failure with its stderr
+    fn run(&self) -> Result<Vec<u8>, PodError> {
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                PodError::Provider(format!("Failed to run {}: {}", self.command, err))
+            })?;
+        // read while waiting, a command filling a pipe would block otherwise
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+        let deadline = Instant::now().checked_add(self.timeout);
+        let status = loop {
+            let status = child.try_wait().map_err(|err| {
+                PodError::Provider(format!("Failed to wait for {}: {}", self.command, err))
+            })?;
+            match status {
+                Some(status) => break status,
+                None if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(PodError::Provider(format!(
+                        "{} didn't finish within {}s",
+                        self.command,
+                        self.timeout.as_secs()
+                    )));
+                }
+                None => thread::sleep(POLL_INTERVAL),
+            }
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            return Err(PodError::Provider(format!(
+                "{} failed with {}: {}",
+                self.command,
+                status,
+                String::from_utf8_lossy(&stderr).trim()
+            )));
+        }
+        Ok(stdout)
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+impl PictureProvider for PictureExecProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        println!("Hacking {}...", self.command);
+        let _ = config;
+        let output = self.run()?;
+
+        // parse stdout as json
+        let response = from_slice::<ExecResponse>(&output)
+            .map_err(|err| PodError::Parse(format!("output of {}: {}", self.command, err)))?;
+        let is_url =
+            response.image.starts_with("http://") || response.image.starts_with("https://");
+        let bytes = if is_url {
+            super::read_bytes(super::http_get(&response.image)?)?
+        } else {
+            fs::read(&response.image).map_err(|err| {
+                PodError::Provider(format!("Failed to read {}: {}", response.image, err))
+            })?
+        };
+        let source_url = response
+            .link
+            .or_else(|| Some(response.image).filter(|_| is_url));
+        let metadata = super::Metadata {
+            title: response.title,
+            description: response.description,
+            source_url,
+            date: None,
+            content_hash: None,
+            provider: None,
+        };
+        // return image bytes and metadata
+        Ok((bytes, metadata))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecResponse {
+    /// Local path or http(s) url of the picture
+    pub image: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Link to the picture source, e.g. the page it was found on
+    pub link: Option<String>,
+}
@@ -0,0 +1,47 @@
+use crate::config::{Config, Provider};
+use crate::error::PodError;
+use crate::picture_io::PictureProvider;
+
+/// Tries providers in order until one returns a picture which can be decoded.
+///
+/// Failed requests, videos and other unusable responses move on to the next provider.
+pub struct PictureFallbackProvider {
+    providers: Vec<Provider>,
+}
+
+impl PictureFallbackProvider {
+    pub fn new(providers: Vec<Provider>) -> Self {
+        PictureFallbackProvider { providers }
+    }
+}
+
+impl PictureProvider for PictureFallbackProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            let result = super::fetch(provider, config).and_then(|(bytes, metadata)| {
+                match image::load_from_memory(&bytes) {
+                    Ok(_) => Ok((bytes, metadata)),
+                    Err(err) => Err(PodError::ImageDecode(err.to_string())),
+                }
+            });
+            match result {
+                Ok(picture) => {
+                    println!("Using picture from {}", provider);
+                    return Ok(picture);
+                }
+                Err(err) => {
+                    eprintln!("Provider {} failed: {}, trying next", provider, err);
+                    errors.push(format!("{}: {}", provider, err));
+                }
+            }
+        }
+        Err(PodError::Provider(format!(
+            "All providers failed ({})",
+            errors.join(", ")
+        )))
+    }
+}
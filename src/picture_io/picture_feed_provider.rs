@@ -0,0 +1,324 @@
+use crate::config::{Config, FeedPick};
+use crate::error::PodError;
+use crate::picture_io::PictureProvider;
+
+use chrono::{DateTime, FixedOffset};
+use rand::seq::IndexedRandom;
+use reqwest::Url;
+use roxmltree::{Document, Node};
+use std::cmp::Reverse;
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const MEDIA_NS: &str = "http://search.yahoo.com/mrss/";
+
+const IMAGE_EXTENSIONS: [&str; 5] = [".jpg", ".jpeg", ".png", ".webp", ".gif"];
+
+/// Provider for RSS 2.0 and Atom feeds, the picture is taken from `media:content`,
+/// `enclosure`, `media:thumbnail` or the first `<img>` in the item description.
+pub struct PictureFeedProvider {
+    url: String,
+    pick: FeedPick,
+}
+
+impl PictureFeedProvider {
+    pub fn new(url: String, pick: FeedPick) -> Self {
+        PictureFeedProvider { url, pick }
+    }
+}
+
+impl PictureProvider for PictureFeedProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        println!("Hacking feed {}...", self.url);
+        let _ = config;
+        let feed_url = Url::parse(&self.url)
+            .map_err(|_| PodError::Provider("Invalid feed url".to_string()))?;
+        let response = super::http_get(feed_url.clone())?;
+
+        let response_text = super::read_text(response)?;
+        let document = Document::parse(&response_text)
+            .map_err(|err| PodError::Parse(format!("feed: {}", err)))?;
+        let items = get_feed_items(&document);
+        let item = match self.pick {
+            // items without a date keep feed order, feeds usually list the newest first
+            FeedPick::Newest => items
+                .iter()
+                .enumerate()
+                .max_by_key(|(index, item)| (item.published, Reverse(*index)))
+                .map(|(_, item)| item),
+            FeedPick::Random => items.choose(&mut rand::rng()),
+        }
+        .ok_or_else(|| PodError::Provider("Feed has no items with an image".to_string()))?;
+        println!(
+            "Picked {}",
+            item.title.as_deref().unwrap_or(&item.image_url)
+        );
+
+        // relative urls are resolved against the feed url
+        let image_url = feed_url
+            .join(&item.image_url)
+            .map_err(|_| PodError::Provider(format!("Invalid image url: {}", item.image_url)))?;
+        let image_response = super::http_get(image_url.clone())?;
+        let bytes = super::read_bytes(image_response)?;
+        let metadata = super::Metadata {
+            title: item.title.clone(),
+            description: item.description.clone(),
+            source_url: Some(image_url.to_string()),
+            date: None,
+            content_hash: None,
+            provider: None,
+        };
+        // return image bytes and metadata
+        Ok((bytes, metadata))
+    }
+}
+
+struct FeedItem {
+    title: Option<String>,
+    description: Option<String>,
+    published: Option<DateTime<FixedOffset>>,
+    image_url: String,
+}
+
+/// Items of an RSS `channel` or entries of an Atom `feed` which have a picture
+fn get_feed_items(document: &Document) -> Vec<FeedItem> {
+    document
+        .descendants()
+        .filter(|node| node.has_tag_name("item") || node.has_tag_name((ATOM_NS, "entry")))
+        .filter_map(|node| {
+            let description = ["description", "summary", "content"]
+                .iter()
+                .find_map(|name| child_text(node, name));
+            let image_url = get_image_url(node)
+                .or_else(|| description.as_deref().and_then(get_html_image_url))?;
+            Some(FeedItem {
+                title: child_text(node, "title").map(|title| strip_html(&title)),
+                description: description
+                    .map(|description| strip_html(&description))
+                    .filter(|description| !description.is_empty()),
+                published: ["pubDate", "published", "updated", "date"]
+                    .iter()
+                    .filter_map(|name| child_text(node, name))
+                    .find_map(|date| parse_date(&date)),
+                image_url,
+            })
+        })
+        .collect()
+}
+
+fn get_image_url(item: Node) -> Option<String> {
+    let media = |name: &str| {
+        item.descendants()
+            .filter(|node| node.has_tag_name((MEDIA_NS, name)))
+            .find(|node| {
+                is_image(
+                    node.attribute("medium"),
+                    node.attribute("type"),
+                    node.attribute("url"),
+                )
+            })
+            .and_then(|node| node.attribute("url"))
+            .map(str::to_string)
+    };
+    let enclosure = || {
+        item.children()
+            .filter(|node| {
+                node.has_tag_name("enclosure")
+                    || (node.has_tag_name((ATOM_NS, "link"))
+                        && node.attribute("rel") == Some("enclosure"))
+            })
+            .find(|node| is_image(None, node.attribute("type"), None))
+            .and_then(|node| node.attribute("url").or(node.attribute("href")))
+            .map(str::to_string)
+    };
+    media("content")
+        .or_else(enclosure)
+        .or_else(|| media("thumbnail"))
+}
+
+/// Media elements without medium and type are accepted when the url looks like a picture
+fn is_image(medium: Option<&str>, mime_type: Option<&str>, url: Option<&str>) -> bool {
+    match (medium, mime_type) {
+        (Some(medium), _) => medium == "image",
+        (None, Some(mime_type)) => mime_type.starts_with("image/"),
+        (None, None) => url.is_none_or(|url| {
+            let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+            IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+                || !path.rsplit('/').next().unwrap_or("").contains('.')
+        }),
+    }
+}
+
+/// `src` of the first `<img>` tag in html content
+fn get_html_image_url(html: &str) -> Option<String> {
+    let tag_start = html.find("<img")?;
+    let tag = &html[tag_start..];
+    let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+    let src = &tag[tag.find("src=")? + 4..];
+    let quote = src.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let src = &src[1..];
+    Some(decode_entities(&src[..src.find(quote)?]))
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+        .map(|child| {
+            child
+                .descendants()
+                .filter(|n| n.is_text())
+                .filter_map(|n| n.text())
+                .collect::<String>()
+        })
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// RSS uses RFC 2822 dates, Atom uses RFC 3339
+fn parse_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .ok()
+}
+
+/// Removes html tags from descriptions, paragraphs and line breaks become new lines
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        let tag = rest[start..end].to_lowercase();
+        if tag.starts_with("<br") || tag.starts_with("</p") {
+            text.push('\n');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    decode_entities(text.trim())
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/">
+  <channel>
+    <title>Photos</title>
+    <item>
+      <title>Enclosure</title>
+      <pubDate>Fri, 14 Feb 2025 08:00:00 +0000</pubDate>
+      <enclosure url="https://example.com/audio.mp3" type="audio/mpeg"/>
+      <enclosure url="https://example.com/enclosure.jpg" type="image/jpeg"/>
+    </item>
+    <item>
+      <title>Media &amp; more</title>
+      <description><![CDATA[<p>First line</p>Second&nbsp;line<br/>]]></description>
+      <media:content url="https://example.com/clip.mp4" medium="video"/>
+      <media:content url="https://example.com/media.png"/>
+      <media:thumbnail url="https://example.com/thumbnail.jpg"/>
+    </item>
+    <item>
+      <title>Description</title>
+      <description>&lt;img alt="x" src="/images/a.jpg?w=1&amp;h=2"&gt;</description>
+    </item>
+    <item>
+      <title>No picture</title>
+      <description>Just text</description>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Photos</title>
+  <entry>
+    <title>Atom enclosure</title>
+    <updated>2025-02-14T08:00:00Z</updated>
+    <link rel="alternate" href="https://example.com/page"/>
+    <link rel="enclosure" type="image/webp" href="https://example.com/atom.webp"/>
+    <summary>A picture</summary>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn rss_items_with_pictures() {
+        let document = Document::parse(RSS).unwrap();
+        let items = get_feed_items(&document);
+        let urls: Vec<&str> = items.iter().map(|item| item.image_url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/enclosure.jpg",
+                "https://example.com/media.png",
+                "/images/a.jpg?w=1&h=2"
+            ]
+        );
+        assert_eq!(items[0].published, parse_date("2025-02-14T08:00:00Z"));
+        assert_eq!(items[1].title.as_deref(), Some("Media & more"));
+        assert_eq!(
+            items[1].description.as_deref(),
+            Some("First line\nSecond line")
+        );
+        assert_eq!(items[2].published, None);
+    }
+
+    #[test]
+    fn atom_link_enclosure() {
+        let document = Document::parse(ATOM).unwrap();
+        let items = get_feed_items(&document);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].image_url, "https://example.com/atom.webp");
+        assert_eq!(items[0].title.as_deref(), Some("Atom enclosure"));
+        assert_eq!(items[0].description.as_deref(), Some("A picture"));
+        assert!(items[0].published.is_some());
+    }
+
+    #[test]
+    fn is_image_checks_medium_type_and_url() {
+        assert!(is_image(Some("image"), Some("video/mp4"), None));
+        assert!(!is_image(Some("video"), Some("image/jpeg"), None));
+        assert!(is_image(None, Some("image/png"), None));
+        assert!(!is_image(None, Some("audio/mpeg"), None));
+        assert!(is_image(
+            None,
+            None,
+            Some("https://example.com/a.JPG?size=large")
+        ));
+        assert!(is_image(None, None, Some("https://example.com/photo/123")));
+        assert!(!is_image(None, None, Some("https://example.com/clip.mp4")));
+        assert!(is_image(None, None, None));
+    }
+
+    #[test]
+    fn html_image_url() {
+        assert_eq!(
+            get_html_image_url(
+                r#"<p>Hi</p><img class="big" src="https://example.com/a.jpg?a=1&amp;b=2">"#
+            )
+            .as_deref(),
+            Some("https://example.com/a.jpg?a=1&b=2")
+        );
+        assert_eq!(
+            get_html_image_url("<img src='b.png'/><img src='c.png'/>").as_deref(),
+            Some("b.png")
+        );
+        assert_eq!(get_html_image_url("<img src=unquoted.png>"), None);
+        assert_eq!(get_html_image_url("<p>No picture</p>"), None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::config::Config;
+use crate::error::PodError;
+use crate::picture_io::PictureProvider;
+
+use reqwest::Url;
+use serde_json::from_str;
+use serde_json::Value;
+
+/// Provider for any json api, picture url and metadata are selected with field paths
+/// like `$.images[0].url`.
+pub struct PictureJsonProvider {
+    url: String,
+    image_path: String,
+    title_path: Option<String>,
+    description_path: Option<String>,
+}
+
+impl PictureJsonProvider {
+    pub fn new(
+        url: String,
+        image_path: String,
+        title_path: Option<String>,
+        description_path: Option<String>,
+    ) -> Self {
+        PictureJsonProvider {
+            url,
+            image_path,
+            title_path,
+            description_path,
+        }
+    }
+
+    /// Picture url at the image path, relative urls are resolved against the api url
+    fn get_image_url(&self, base_url: &Url, json: &Value) -> Result<Url, PodError> {
+        let image_url = select(json, &self.image_path)
+            .and_then(value_to_string)
+            .ok_or_else(|| {
+                PodError::Provider(format!("No image url found at {}", self.image_path))
+            })?;
+        base_url
+            .join(&image_url)
+            .map_err(|_| PodError::Provider(format!("Invalid image url: {}", image_url)))
+    }
+
+    fn select_text(&self, json: &Value, path: &Option<String>) -> Option<String> {
+        let path = path.as_ref()?;
+        let text = select(json, path).and_then(value_to_string);
+        if text.is_none() {
+            eprintln!("Nothing found at {} in {}", path, self.url);
+        }
+        text
+    }
+}
+
+impl PictureProvider for PictureJsonProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        println!("Hacking {}...", self.url);
+        let _ = config;
+        let base_url = Url::parse(&self.url)
+            .map_err(|_| PodError::Provider("Invalid json url".to_string()))?;
+        let response = super::http_get(base_url.clone())?;
+
+        let response_text = super::read_text(response)?;
+        let json = from_str::<Value>(&response_text)
+            .map_err(|err| PodError::Parse(format!("json response: {}", err)))?;
+        let image_url = self.get_image_url(&base_url, &json)?;
+
+        // download image from url
+        let image_response = super::http_get(image_url.clone())?;
+        let bytes = super::read_bytes(image_response)?;
+        let metadata = super::Metadata {
+            title: self.select_text(&json, &self.title_path),
+            description: self.select_text(&json, &self.description_path),
+            source_url: Some(image_url.to_string()),
+            date: None,
+            content_hash: None,
+            provider: None,
+        };
+        // return image bytes and metadata
+        Ok((bytes, metadata))
+    }
+}
+
+/// Selects a value with a JSONPath-like path: `$.images[0].url`, `images.0.url` or `$["some key"]`.
+/// Negative indices count from the end of an array.
+fn select<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = json;
+    let mut rest = path;
+    while !rest.is_empty() {
+        let segment;
+        if let Some(bracket) = rest.strip_prefix('[') {
+            let end = bracket.find(']')?;
+            segment = bracket[..end]
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'');
+            rest = &bracket[end + 1..];
+        } else {
+            let field = rest.strip_prefix('.').unwrap_or(rest);
+            let end = field.find(['.', '[']).unwrap_or(field.len());
+            segment = &field[..end];
+            rest = &field[end..];
+            if segment.is_empty() {
+                continue;
+            }
+        }
+        current = match current {
+            Value::Array(items) => {
+                let index = segment.parse::<i64>().ok()?;
+                let index = if index < 0 {
+                    items.len() as i64 + index
+                } else {
+                    index
+                };
+                items.get(usize::try_from(index).ok()?)?
+            }
+            Value::Object(fields) => fields.get(segment)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response() -> Value {
+        json!({
+            "images": [
+                {"url": "/pictures/first.jpg", "title": "First"},
+                {"url": "https://cdn.example/last.jpg", "title": "Last", "views": 42}
+            ],
+            "thumbnail": "thumbs/today.jpg",
+            "some key": {"flag": true},
+            "empty": null
+        })
+    }
+
+    #[test]
+    fn select_follows_paths() {
+        let json = response();
+        let text = |path| select(&json, path).and_then(value_to_string);
+        assert_eq!(text("$.images[0].title").as_deref(), Some("First"));
+        assert_eq!(text("images.1.title").as_deref(), Some("Last"));
+        assert_eq!(text("$.images[-1].views").as_deref(), Some("42"));
+        assert_eq!(
+            text("$.images[-2].url").as_deref(),
+            Some("/pictures/first.jpg")
+        );
+        assert_eq!(text("$[\"some key\"].flag").as_deref(), Some("true"));
+        assert_eq!(text("$['some key']['flag']").as_deref(), Some("true"));
+        assert_eq!(text("$.images[-3].url"), None);
+        assert_eq!(text("$.images[2].url"), None);
+        assert_eq!(text("$.images.first"), None);
+        assert_eq!(text("$.empty"), None);
+        assert_eq!(text("$.images[0"), None);
+        assert_eq!(select(&json, "$"), Some(&json));
+    }
+
+    #[test]
+    fn image_url_is_resolved_against_api_url() {
+        let json = response();
+        let base_url = Url::parse("https://api.example/v1/today.json").unwrap();
+        let image_url = |path: &str| {
+            PictureJsonProvider::new(base_url.to_string(), path.to_string(), None, None)
+                .get_image_url(&base_url, &json)
+                .map(|url| url.to_string())
+        };
+        assert_eq!(
+            image_url("$.images[0].url").unwrap(),
+            "https://api.example/pictures/first.jpg"
+        );
+        assert_eq!(
+            image_url("$.thumbnail").unwrap(),
+            "https://api.example/v1/thumbs/today.jpg"
+        );
+        assert_eq!(
+            image_url("$.images[-1].url").unwrap(),
+            "https://cdn.example/last.jpg"
+        );
+        assert!(matches!(
+            image_url("$.images[0].missing"),
+            Err(PodError::Provider(_))
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::config::{Config, LocalOrder};
+use crate::error::PodError;
+use crate::filter;
+use crate::picture_io::PictureProvider;
+
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+use serde_json::from_str;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IMAGE_EXTENSIONS: [&str; 8] = ["jpg", "jpeg", "png", "bmp", "gif", "webp", "tif", "tiff"];
+
+pub struct PictureLocalProvider {
+    dir: PathBuf,
+    order: LocalOrder,
+}
+
+impl PictureLocalProvider {
+    pub fn new(dir: PathBuf, order: LocalOrder) -> Self {
+        PictureLocalProvider { dir, order }
+    }
+
+    /// All images in the directory sorted by file name
+    fn get_images(&self) -> Result<Vec<PathBuf>, PodError> {
+        let mut images: Vec<PathBuf> = fs::read_dir(&self.dir)
+            .map_err(|err| {
+                PodError::Provider(format!("Failed to read {}: {}", self.dir.display(), err))
+            })?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && is_image(path))
+            .collect();
+        images.sort();
+        Ok(images)
+    }
+
+    fn pick_random(&self, images: &[PathBuf]) -> Result<PathBuf, PodError> {
+        images
+            .choose(&mut rand::rng())
+            .cloned()
+            .ok_or_else(|| PodError::Provider("No images found in directory".to_string()))
+    }
+
+    /// File remembering the last sequential pick, one per directory in `pod/sequence`
+    /// of the user state directory (the data directory where there is none)
+    fn get_sequence_file(&self) -> PathBuf {
+        let dir = fs::canonicalize(&self.dir).unwrap_or_else(|_| self.dir.clone());
+        let hash = filter::content_hash(dir.to_string_lossy().as_bytes());
+        dirs::state_dir()
+            .or_else(dirs::data_dir)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("pod")
+            .join("sequence")
+            .join(format!("{}.txt", &hash[..16]))
+    }
+
+    /// Next image after the one picked last time, wraps around at the end of the directory
+    fn pick_sequential(
+        &self,
+        images: &[PathBuf],
+        sequence_file: &Path,
+    ) -> Result<PathBuf, PodError> {
+        let last = fs::read_to_string(sequence_file).unwrap_or_default();
+        let last = last.trim();
+        let next = images
+            .iter()
+            .find(|path| file_name(path).as_str() > last)
+            .or(images.first())
+            .cloned()
+            .ok_or_else(|| PodError::Provider("No images found in directory".to_string()))?;
+        let written = sequence_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(sequence_file, file_name(&next)));
+        if let Err(err) = written {
+            eprintln!("Failed to remember sequential pick: {:?}", err);
+        }
+        Ok(next)
+    }
+
+    /// Image named after today, `2025-02-14.jpg`, `20250214.jpg` or `02-14.jpg` for yearly pictures
+    fn pick_by_date(&self, images: &[PathBuf]) -> Result<PathBuf, PodError> {
+        let today = chrono::Local::now();
+        ["%Y-%m-%d", "%Y%m%d", "%m-%d"]
+            .iter()
+            .map(|format| today.format(format).to_string())
+            .find_map(|date| images.iter().find(|path| file_stem(path).contains(&date)))
+            .cloned()
+            .ok_or_else(|| {
+                PodError::Provider(
+                    "No image named after today's date found in directory".to_string(),
+                )
+            })
+    }
+}
+
+impl PictureProvider for PictureLocalProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        println!("Hacking local directory {}...", self.dir.display());
+        let _ = config;
+        let images = self.get_images()?;
+        let path = match self.order {
+            LocalOrder::Random => self.pick_random(&images)?,
+            LocalOrder::Sequential => self.pick_sequential(&images, &self.get_sequence_file())?,
+            LocalOrder::Date => self.pick_by_date(&images)?,
+        };
+        println!("Picked {}", path.display());
+        let bytes = fs::read(&path).map_err(|err| {
+            PodError::Provider(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        let metadata = get_metadata(&path);
+        Ok((bytes, metadata))
+    }
+}
+
+/// Reads metadata from a `<name>.json` or `<name>.txt` sidecar, falls back to the file name as title
+fn get_metadata(path: &Path) -> super::Metadata {
+    let json_sidecar = path.with_extension("json");
+    if let Ok(text) = fs::read_to_string(&json_sidecar) {
+        match from_str::<LocalSidecar>(&text) {
+            Ok(sidecar) => {
+                return super::Metadata {
+                    title: sidecar.title,
+                    description: sidecar.description,
+                    source_url: None,
+                    date: None,
+                    content_hash: None,
+                    provider: None,
+                }
+            }
+            Err(err) => eprintln!("Failed to parse {}: {}", json_sidecar.display(), err),
+        }
+    }
+    // first line is the title, the rest is the description
+    if let Ok(text) = fs::read_to_string(path.with_extension("txt")) {
+        let (title, description) = text.split_once('\n').unwrap_or((&text, ""));
+        let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        return super::Metadata {
+            title: non_empty(title),
+            description: non_empty(description),
+            source_url: None,
+            date: None,
+            content_hash: None,
+            provider: None,
+        };
+    }
+    super::Metadata {
+        title: Some(file_stem(path).replace(['_', '-'], " ")),
+        description: None,
+        source_url: None,
+        date: None,
+        content_hash: None,
+        provider: None,
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct LocalSidecar {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pod-local-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn sequence_file_per_directory() {
+        let first = temp_dir("first");
+        let second = temp_dir("second");
+        let sequence_file = |dir: &Path| {
+            PictureLocalProvider::new(dir.to_path_buf(), LocalOrder::Sequential).get_sequence_file()
+        };
+        assert_ne!(sequence_file(&first), sequence_file(&second));
+        assert_eq!(sequence_file(&first), sequence_file(&first.join(".")));
+        fs::remove_dir_all(first).unwrap();
+        fs::remove_dir_all(second).unwrap();
+    }
+
+    #[test]
+    fn sequential_picks_of_directories_are_independent() {
+        let state = temp_dir("state");
+        let pick = |dir: &str, names: &[&str]| {
+            let provider = PictureLocalProvider::new(PathBuf::from(dir), LocalOrder::Sequential);
+            let images: Vec<PathBuf> = names.iter().map(|name| Path::new(dir).join(name)).collect();
+            let picked = provider
+                .pick_sequential(&images, &state.join(dir).join("sequence.txt"))
+                .unwrap();
+            file_name(&picked)
+        };
+        let a = ["a1.jpg", "a2.jpg", "a3.jpg"];
+        let b = ["b1.jpg", "b2.jpg"];
+        assert_eq!(pick("a", &a), "a1.jpg");
+        assert_eq!(pick("b", &b), "b1.jpg");
+        assert_eq!(pick("a", &a), "a2.jpg");
+        assert_eq!(pick("b", &b), "b2.jpg");
+        assert_eq!(pick("a", &a), "a3.jpg");
+        assert_eq!(pick("b", &b), "b1.jpg");
+        assert_eq!(pick("a", &a), "a1.jpg");
+        fs::remove_dir_all(state).unwrap();
+    }
+}
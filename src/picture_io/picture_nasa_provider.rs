@@ -1,94 +1,210 @@
+use crate::cache::CacheKey;
 use crate::config::Config;
+use crate::error::PodError;
 use crate::picture_io::PictureProvider;
+use crate::secret::Secret;
 
-use reqwest;
+use chrono::{FixedOffset, NaiveDate, Utc};
+use rand::seq::IndexedRandom;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::from_str;
+use serde_json::Value;
 use std::fmt::Debug;
-use std::io::Error;
 
-pub struct PictureNasaProvider;
+/// Offset of the APOD calendar, the picture changes at midnight US Eastern time.
+/// Daylight saving time is assumed all year, an hour too early only costs cache misses.
+const APOD_UTC_OFFSET_SECONDS: i32 = -4 * 60 * 60;
+
+/// Which APOD is requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NasaQuery {
+    /// Picture of today, a random picture on video days
+    Today,
+    /// Random picture from the whole archive
+    Random,
+    /// Picture of a specific day
+    Date(NaiveDate),
+    /// Random picture between two days, the end defaults to today
+    Range(NaiveDate, Option<NaiveDate>),
+}
+
+pub struct PictureNasaProvider {
+    api_key: Secret,
+    query: NasaQuery,
+    video_thumbnail: bool,
+}
 
 impl PictureNasaProvider {
-    pub fn new() -> Self {
-        PictureNasaProvider
+    pub fn new(api_key: Secret, query: NasaQuery, video_thumbnail: bool) -> Self {
+        PictureNasaProvider {
+            api_key,
+            query,
+            video_thumbnail,
+        }
+    }
+
+    fn get_base_url(&self) -> String {
+        let thumbs = if self.video_thumbnail {
+            "&thumbs=true"
+        } else {
+            ""
+        };
+        format!(
+            "https://api.nasa.gov/planetary/apod?api_key={}{}",
+            self.api_key.expose(),
+            thumbs
+        )
+    }
+
+    fn get_url(&self) -> String {
+        let base_url = self.get_base_url();
+        match self.query {
+            NasaQuery::Today => base_url,
+            NasaQuery::Random => self.get_random_url(),
+            NasaQuery::Date(date) => format!("{}&date={}", base_url, date),
+            NasaQuery::Range(start_date, Some(end_date)) => format!(
+                "{}&start_date={}&end_date={}",
+                base_url, start_date, end_date
+            ),
+            NasaQuery::Range(start_date, None) => {
+                format!("{}&start_date={}", base_url, start_date)
+            }
+        }
+    }
+
+    fn get_random_url(&self) -> String {
+        format!("{}&count=1", self.get_base_url())
+    }
+
+    fn get_response(&self) -> Result<String, PodError> {
+        match self.query {
+            NasaQuery::Random => self.get_random_response(),
+            NasaQuery::Range(..) => self.get_range_response(),
+            NasaQuery::Today | NasaQuery::Date(_) => {
+                // fetch data from NASA API
+                let response = super::http_get(self.get_url())?;
+                // parse response as json
+                let response_text = super::read_text(response)?;
+                let media_type = from_str::<NasaMediaType>(&response_text)
+                    .map_err(|err| PodError::Parse(format!("NASA media type: {}", err)))?;
+                if media_type.media_type != "video" || self.video_thumbnail {
+                    Ok(response_text)
+                } else if let NasaQuery::Date(date) = self.query {
+                    Err(PodError::Provider(format!("APOD of {} is a video", date)))
+                } else {
+                    println!("Got a video today, hacking a random image instead");
+                    self.get_random_response()
+                }
+            }
+        }
+    }
+
+    fn get_random_response(&self) -> Result<String, PodError> {
+        let response = super::http_get(self.get_random_url())?;
+        let response_text = super::read_text(response)?;
+        // the random response is an array with a single day
+        let days = from_str::<Vec<Value>>(&response_text)
+            .map_err(|err| PodError::Parse(format!("NASA random picture: {}", err)))?;
+        days.first()
+            .map(|day| day.to_string())
+            .ok_or_else(|| PodError::Provider("NASA API returned no random picture".to_string()))
+    }
+
+    /// Random image of a date range, videos in the range are skipped unless their thumbnail is used
+    fn get_range_response(&self) -> Result<String, PodError> {
+        let response = super::http_get(self.get_url())?;
+        let response_text = super::read_text(response)?;
+        let days = from_str::<Vec<Value>>(&response_text)
+            .map_err(|err| PodError::Parse(format!("NASA date range: {}", err)))?;
+        let images: Vec<&Value> = days
+            .iter()
+            .filter(|day| {
+                day["media_type"] == "image"
+                    || (self.video_thumbnail && day["thumbnail_url"].is_string())
+            })
+            .collect();
+        println!("Got {} images in date range", images.len());
+        images
+            .choose(&mut rand::rng())
+            .map(|day| day.to_string())
+            .ok_or_else(|| PodError::Provider("No images in date range".to_string()))
     }
 }
 
 impl PictureProvider for PictureNasaProvider {
+    /// Pictures of a day are cached, random pictures are not
+    fn cache_key(&self, config: &Config) -> Option<CacheKey> {
+        let _ = config;
+        let date = match self.query {
+            NasaQuery::Today => get_apod_today(),
+            NasaQuery::Date(date) => date,
+            NasaQuery::Random | NasaQuery::Range(..) => return None,
+        };
+        let variant = if self.video_thumbnail {
+            "thumbnail"
+        } else {
+            "picture"
+        };
+        Some(CacheKey::new(date, variant))
+    }
+
     fn get_picture_with_metadata(
         &self,
         config: &Config,
-    ) -> std::io::Result<(Vec<u8>, super::Metadata)> {
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
         println!("Hacking Nasa server...");
-        let response = get_nasa_response(&config);
+        let _ = config;
+        let response = self.get_response()?;
+        let media_type = from_str::<NasaMediaType>(&response)
+            .map_err(|err| PodError::Parse(format!("NASA media type: {}", err)))?;
+        if media_type.media_type == "video" {
+            return get_video_thumbnail(&response);
+        }
         // parse response as json
-        let data = from_str::<NasaImageResponse>(&response);
-        // if data is not parsed successfully print error message and exit
-        let data = match data {
-            Ok(data) => data,
-            Err(_) => {
-                // return result with error
-                return Err(Error::new(
-                    std::io::ErrorKind::Other,
-                    "Failed to parse and save image from NASA API",
-                ));
-            }
-        };
+        let data = from_str::<NasaImageResponse>(&response)
+            .map_err(|err| PodError::Parse(format!("NASA image: {}", err)))?;
         // download image from url
-        let image_response = reqwest::blocking::get(&data.hdurl).expect("Failed to send request");
-        let bytes = image_response.bytes().expect("Failed to read image bytes");
+        let image_response = super::http_get(&data.hdurl)?;
+        let bytes = super::read_bytes(image_response)?;
         let metadata = super::Metadata {
             title: Some(data.title.clone()),
             description: Some(data.explanation.clone()),
+            source_url: Some(data.hdurl.clone()),
+            date: NaiveDate::parse_from_str(&data.date, "%Y-%m-%d").ok(),
+            content_hash: None,
+            provider: None,
         };
         // return image bytes and metadata
-        Ok((bytes.to_vec(), metadata))
+        Ok((bytes, metadata))
     }
 }
 
-fn get_nasa_response(config: &Config) -> String {
-    if config.is_nasa_random() {
-        get_nasa_random_response(config)
-    } else {
-        let url = config.get_nasa_url();
-        // fetch data from NASA API
-        let response = reqwest::blocking::get(&url).expect("Failed to send request");
-        // if response is not successful print error message and exit
-        if !response.status().is_success() {
-            eprintln!("Failed to fetch data from NASA API");
-            std::process::exit(1);
-        }
-        // parse response as json
-        let response_text = response.text().unwrap();
-        let media_type =
-            from_str::<NasaMediaType>(&response_text).expect("Failed to parse media type");
-        if media_type.media_type == "video" {
-            println!("Got a video today, hacking a random image instead");
-            get_nasa_random_response(config)
-        } else {
-            response_text
-        }
-    }
+/// Date of today's APOD, which is the date it is cached under
+fn get_apod_today() -> NaiveDate {
+    let offset = FixedOffset::east_opt(APOD_UTC_OFFSET_SECONDS).expect("offset within a day");
+    Utc::now().with_timezone(&offset).date_naive()
 }
 
-fn get_nasa_random_response(config: &Config) -> String {
-    let random_response = |res: String| {
-        // trim end of response to remove trailing \n
-        let response_text = res.trim_end().to_string();
-        // remove beginning and trailing [ and ] from response
-        response_text[1..response_text.len() - 1].to_string()
+/// Thumbnail of a video day (YouTube or Vimeo still) with the explanation as metadata
+fn get_video_thumbnail(response: &str) -> Result<(Vec<u8>, super::Metadata), PodError> {
+    let data = from_str::<NasaVideoResponse>(response)
+        .map_err(|err| PodError::Parse(format!("NASA video: {}", err)))?;
+    let thumbnail_url = data.thumbnail_url.ok_or_else(|| {
+        PodError::Provider("NASA API returned a video without a thumbnail".to_string())
+    })?;
+    println!("Got a video, hacking its thumbnail");
+    let image_response = super::http_get(&thumbnail_url)?;
+    let bytes = super::read_bytes(image_response)?;
+    let metadata = super::Metadata {
+        title: Some(data.title),
+        description: Some(data.explanation),
+        source_url: Some(data.url),
+        date: NaiveDate::parse_from_str(&data.date, "%Y-%m-%d").ok(),
+        content_hash: None,
+        provider: None,
     };
-    let url = config.get_nasa_random_url();
-    let response = reqwest::blocking::get(&url).expect("Failed to send request");
-    // if response is not successful print error message and exit
-    if !response.status().is_success() {
-        eprintln!("Failed to fetch data from NASA API");
-        std::process::exit(1);
-    }
-    let response_text = response.text().unwrap();
-    random_response(response_text)
+    Ok((bytes, metadata))
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -110,6 +226,7 @@ pub struct NasaImageResponse {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NasaVideoResponse {
+    #[serde(default)]
     pub copyright: String,
     pub date: String,
     pub explanation: String,
@@ -119,6 +236,9 @@ pub struct NasaVideoResponse {
     pub service_version: String,
     pub title: String,
     pub url: String,
+    /// Only returned when requested with `thumbs=true`
+    #[serde(rename = "thumbnail_url")]
+    pub thumbnail_url: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,34 @@
+use crate::config::{Config, WeightedProvider};
+use crate::error::PodError;
+use crate::picture_io::PictureProvider;
+
+use rand::distr::weighted::WeightedIndex;
+use rand::distr::Distribution;
+
+/// Picks one provider at random on every run, providers with a higher weight are picked more often
+pub struct PictureRandomProvider {
+    providers: Vec<WeightedProvider>,
+}
+
+impl PictureRandomProvider {
+    pub fn new(providers: Vec<WeightedProvider>) -> Self {
+        PictureRandomProvider { providers }
+    }
+}
+
+impl PictureProvider for PictureRandomProvider {
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        let weights = WeightedIndex::new(self.providers.iter().map(|p| p.weight))
+            .map_err(|err| PodError::Provider(format!("Invalid provider weights: {}", err)))?;
+        let picked = &self.providers[weights.sample(&mut rand::rng())];
+        let total: u32 = self.providers.iter().map(|p| p.weight).sum();
+        println!(
+            "Picked provider {} (weight {} of {})",
+            picked.provider, picked.weight, total
+        );
+        super::fetch(&picked.provider, config)
+    }
+}
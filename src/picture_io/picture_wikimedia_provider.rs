@@ -0,0 +1,326 @@
+use crate::cache::CacheKey;
+use crate::config::{Config, DEFAULT_WIKIMEDIA_API_URL};
+use crate::error::PodError;
+use crate::filter;
+use crate::picture_io::PictureProvider;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_str;
+use std::collections::HashMap;
+
+/// Wikimedia asks API clients to identify themselves, requests without a user agent are rejected
+const USER_AGENT: &str = concat!(
+    "pod/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/nevarman/pod)"
+);
+
+pub struct PictureWikimediaProvider {
+    api_url: String,
+}
+
+impl PictureWikimediaProvider {
+    pub fn new(api_url: String) -> Self {
+        PictureWikimediaProvider { api_url }
+    }
+
+    /// Featured content url for today, e.g. `<api_url>/2025/02/14`
+    fn get_featured_url(&self) -> String {
+        format!(
+            "{}/{}",
+            self.api_url.trim_end_matches('/'),
+            chrono::Local::now().format("%Y/%m/%d")
+        )
+    }
+}
+
+impl PictureProvider for PictureWikimediaProvider {
+    /// Pictures of the default api are cached per day, other apis get their own entries
+    fn cache_key(&self, config: &Config) -> Option<CacheKey> {
+        let _ = config;
+        let variant = if self.api_url == DEFAULT_WIKIMEDIA_API_URL {
+            "featured".to_string()
+        } else {
+            // a stable hash, the entry has to be found by later versions of pod
+            let hash = filter::content_hash(self.api_url.as_bytes());
+            format!("featured_{}", &hash[..16])
+        };
+        Some(CacheKey::new(chrono::Local::now().date_naive(), &variant))
+    }
+
+    fn get_picture_with_metadata(
+        &self,
+        config: &Config,
+    ) -> Result<(Vec<u8>, super::Metadata), PodError> {
+        println!("Hacking Wikimedia server...");
+        let _ = config;
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|err| PodError::Network(err.to_string()))?;
+        // fetch featured content of the day
+        let response = super::send(client.get(self.get_featured_url()))?;
+
+        // parse response as json
+        let response_text = super::read_text(response)?;
+        let featured = from_str::<WikimediaFeaturedResponse>(&response_text)
+            .map_err(|err| PodError::Parse(format!("Wikimedia featured content: {}", err)))?;
+        let image = featured.image.ok_or_else(|| {
+            PodError::Provider("Wikimedia has no picture of the day today".to_string())
+        })?;
+
+        // download image from url
+        let image_response = super::send(client.get(image.get_download_url()))?;
+        let bytes = super::read_bytes(image_response)?;
+        let metadata = super::Metadata {
+            title: Some(image.get_caption()),
+            description: Some(image.get_description()),
+            source_url: Some(image.get_source_url()),
+            date: Some(chrono::Local::now().date_naive()),
+            content_hash: None,
+            provider: None,
+        };
+        // return image bytes and metadata
+        Ok((bytes, metadata))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaFeaturedResponse {
+    pub image: Option<WikimediaImage>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaImage {
+    pub title: String,
+    pub thumbnail: WikimediaSource,
+    pub image: WikimediaSource,
+    #[serde(default)]
+    pub file_page: String,
+    pub artist: Option<WikimediaText>,
+    pub license: Option<WikimediaLicense>,
+    pub description: Option<WikimediaText>,
+    pub structured: Option<WikimediaStructured>,
+}
+
+impl WikimediaImage {
+    /// Originals may be svg or tiff files which can't be decoded, use the rendered thumbnail for those
+    fn get_download_url(&self) -> &str {
+        let source = self.image.source.to_lowercase();
+        if source.ends_with(".jpg") || source.ends_with(".jpeg") || source.ends_with(".png") {
+            &self.image.source
+        } else {
+            &self.thumbnail.source
+        }
+    }
+
+    /// File page on Commons, which credits the author, or the download url
+    fn get_source_url(&self) -> String {
+        if self.file_page.is_empty() {
+            self.get_download_url().to_string()
+        } else {
+            self.file_page.clone()
+        }
+    }
+
+    /// English caption if available, otherwise the file name without the `File:` prefix and extension
+    fn get_caption(&self) -> String {
+        if let Some(caption) = self
+            .structured
+            .as_ref()
+            .and_then(|s| s.captions.get("en"))
+            .filter(|c| !c.trim().is_empty())
+        {
+            return caption.trim().to_string();
+        }
+        let name = self.title.trim_start_matches("File:");
+        name.rsplit_once('.')
+            .map_or(name, |(stem, _)| stem)
+            .replace('_', " ")
+    }
+
+    /// Description text followed by the author and licence line
+    fn get_description(&self) -> String {
+        let description = self.description.as_ref().map_or("", |d| d.text.trim());
+        let artist = self.artist.as_ref().map(|a| a.text.trim());
+        let license = self.license.as_ref().map(|l| l.license_type.trim());
+        let attribution = match (artist, license) {
+            (Some(artist), Some(license)) => format!("© {}, {}", artist, license),
+            (Some(artist), None) => format!("© {}", artist),
+            (None, Some(license)) => license.to_string(),
+            (None, None) => String::new(),
+        };
+        [description, attribution.as_str()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaSource {
+    pub source: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaText {
+    #[serde(default)]
+    pub html: String,
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaLicense {
+    #[serde(rename = "type")]
+    pub license_type: String,
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WikimediaStructured {
+    #[serde(default)]
+    pub captions: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Featured content response of the api, trimmed to the fields pod reads
+    const FEATURED_RESPONSE: &str = r#"{
+        "tfa": {"title": "Not_a_picture"},
+        "image": {
+            "title": "File:Mandelbrot_set_(Blue).svg",
+            "thumbnail": {"source": "{base}/thumb/640px-Mandelbrot_set_(Blue).svg.png", "width": 640, "height": 480},
+            "image": {"source": "{base}/Mandelbrot_set_(Blue).svg", "width": 1200, "height": 900},
+            "file_page": "https://commons.wikimedia.org/wiki/File:Mandelbrot_set_(Blue).svg",
+            "artist": {"html": "<a href=\"//commons.wikimedia.org/wiki/User:Example\">Example</a>", "text": "Example"},
+            "license": {"type": "CC BY-SA 4.0", "code": "cc-by-sa-4.0", "url": "https://creativecommons.org/licenses/by-sa/4.0"},
+            "description": {"html": "The <b>Mandelbrot set</b> in blue", "text": "The Mandelbrot set in blue", "lang": "en"},
+            "structured": {"captions": {"en": " Mandelbrot set rendered in blue ", "de": "Mandelbrot-Menge"}}
+        }
+    }"#;
+
+    const PICTURE: &[u8] = b"\x89PNG\r\n\x1a\nrendered thumbnail";
+
+    /// Serves the recorded response and the picture, returns the base url
+    /// and a handle returning the requested paths
+    fn serve(requests: usize) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let featured = FEATURED_RESPONSE.replace("{base}", &base);
+        let handle = thread::spawn(move || {
+            let mut paths = Vec::new();
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request_line.split(' ').nth(1).unwrap().to_string();
+                let (content_type, body) = if path.starts_with("/featured/") {
+                    ("application/json", featured.as_bytes())
+                } else if path.starts_with("/thumb/") {
+                    ("image/png", PICTURE)
+                } else {
+                    ("text/plain", &b"not found"[..])
+                };
+                let status = if body == b"not found" {
+                    "404 Not Found"
+                } else {
+                    "200 OK"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    content_type,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+                paths.push(path);
+            }
+            paths
+        });
+        (base, handle)
+    }
+
+    #[test]
+    fn fetches_picture_of_the_day_from_api() {
+        let (base, handle) = serve(2);
+        let provider = PictureWikimediaProvider::new(format!("{}/featured/", base));
+        let config = Config::try_parse_from(["pod", "wikimedia"]).unwrap();
+
+        let (bytes, metadata) = provider.get_picture_with_metadata(&config).unwrap();
+        assert_eq!(bytes, PICTURE);
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Mandelbrot set rendered in blue")
+        );
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("The Mandelbrot set in blue\n\n© Example, CC BY-SA 4.0")
+        );
+        assert_eq!(
+            metadata.source_url.as_deref(),
+            Some("https://commons.wikimedia.org/wiki/File:Mandelbrot_set_(Blue).svg")
+        );
+
+        let paths = handle.join().unwrap();
+        let today = chrono::Local::now().format("%Y/%m/%d").to_string();
+        assert_eq!(
+            paths,
+            [
+                format!("/featured/{}", today),
+                "/thumb/640px-Mandelbrot_set_(Blue).svg.png".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_file_name_and_original() {
+        let featured: WikimediaFeaturedResponse =
+            from_str(&FEATURED_RESPONSE.replace("{base}", "https://upload.example")).unwrap();
+        let mut image = featured.image.unwrap();
+        assert_eq!(
+            image.get_download_url(),
+            "https://upload.example/thumb/640px-Mandelbrot_set_(Blue).svg.png"
+        );
+
+        image.image.source = "https://upload.example/Sunset.JPG".to_string();
+        image.structured = None;
+        image.description = None;
+        image.license = None;
+        image.file_page.clear();
+        assert_eq!(
+            image.get_download_url(),
+            "https://upload.example/Sunset.JPG"
+        );
+        assert_eq!(image.get_source_url(), "https://upload.example/Sunset.JPG");
+        assert_eq!(image.get_caption(), "Mandelbrot set (Blue)");
+        assert_eq!(image.get_description(), "© Example");
+    }
+}
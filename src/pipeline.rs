@@ -0,0 +1,113 @@
+use crate::config::Config;
+use crate::error::PodError;
+use crate::history::History;
+use crate::modifiers::{
+    metadata_modifier::MetaDataModifier, size_modifier::SizeModifier, ImageModifier,
+};
+use crate::picture_io::{self, Metadata};
+
+use image::DynamicImage;
+use std::path::Path;
+
+/// Fetches the picture of the configured provider and decodes it
+pub fn fetch(config: &Config) -> Result<(DynamicImage, Metadata), PodError> {
+    let (buffer, metadata) = picture_io::get_picture_of_day_with_metadata(config)?;
+    println!("Hacking complete");
+    if let Some(source_url) = &metadata.source_url {
+        println!("Picture source: {}", source_url);
+    }
+    let image =
+        image::load_from_memory(&buffer).map_err(|err| PodError::ImageDecode(err.to_string()))?;
+    Ok((image, metadata))
+}
+
+/// Applies the configured modifiers, size first and metadata last, returns the applied ones
+pub fn apply_modifiers(
+    config: &Config,
+    image: &mut DynamicImage,
+    metadata: Metadata,
+) -> Result<Vec<String>, PodError> {
+    println!("Applying modifiers");
+    let mut applied = Vec::new();
+    // apply size_modifier first
+    if config.fit_to_screen_size.unwrap_or(false) {
+        // get screen size
+        // TODO from actual screen?
+        let (w, h) = match (config.width, config.height) {
+            (Some(w), Some(h)) => (w, h),
+            _ => {
+                return Err(PodError::Config(
+                    "--width and --height are needed to fit the screen size".to_string(),
+                ))
+            }
+        };
+        let fit_mode = config.get_fit_mode(metadata.provider.as_deref());
+        let size_modifier = SizeModifier::new(w, h).with_fit_mode(fit_mode);
+        size_modifier.modify(image)?;
+        applied.push(format!("fit {}x{} {}", w, h, fit_mode));
+    }
+    // apply metadata_modifier next
+    if config.add_metadata.unwrap_or(false) {
+        let metadata_modifier = MetaDataModifier::new(metadata, config);
+        metadata_modifier.modify(image)?;
+        applied.push("metadata".to_string());
+    }
+    Ok(applied)
+}
+
+/// Saves the picture as jpeg
+pub fn save(image: &DynamicImage, path: &str) -> Result<(), PodError> {
+    println!("Saving picture to: {}", path);
+    // jpeg has no alpha channel
+    DynamicImage::ImageRgb8(image.to_rgb8())
+        .save_with_format(path, image::ImageFormat::Jpeg)
+        .map_err(|err| PodError::Save(format!("{}: {}", path, err)))
+}
+
+pub fn set_wallpaper(path: &str) -> Result<(), PodError> {
+    println!("Setting wallpaper");
+    wallpaper::set_from_path(path).map_err(|err| PodError::Wallpaper(err.to_string()))
+}
+
+/// Fetches, modifies, saves and sets the picture as wallpaper, returns the saved picture path
+pub fn run(config: &Config) -> Result<String, PodError> {
+    let (image, metadata) = fetch(config)?;
+    apply(config, image, metadata)
+}
+
+/// Modifies, saves and sets a fetched picture as wallpaper, returns the saved picture path
+pub fn apply(
+    config: &Config,
+    mut image: DynamicImage,
+    metadata: Metadata,
+) -> Result<String, PodError> {
+    let original = image.clone();
+    let modifiers = apply_modifiers(config, &mut image, metadata.clone())?;
+    // save and set background
+    let path = config.get_picture_file_name();
+    save(&image, &path)?;
+    set_wallpaper(&path)?;
+    record(config, &metadata, &original, &path, modifiers);
+    Ok(path)
+}
+
+/// Adds the wallpaper to the history, a failure is only reported as the wallpaper is set already
+fn record(
+    config: &Config,
+    metadata: &Metadata,
+    original: &DynamicImage,
+    path: &str,
+    modifiers: Vec<String>,
+) {
+    let provider = metadata.provider.clone().unwrap_or_else(|| {
+        config
+            .get_provider()
+            .map_or("unknown".to_string(), |provider| provider.to_string())
+    });
+    let result = History::open()
+        .and_then(|history| history.add(provider, metadata, original, Path::new(path), modifiers));
+    match result {
+        Ok(entry) => println!("Recorded as history entry {}", entry.id),
+        Err(err) => eprintln!("Failed to add wallpaper to history: {}", err),
+    }
+}
@@ -0,0 +1,54 @@
+use crate::config::{Config, ReapplyArgs};
+use crate::error::PodError;
+use crate::history::{History, HistoryEntry};
+use crate::pipeline;
+
+/// Sets a wallpaper of the history again without fetching and makes it the current one
+pub fn apply(config: &Config, id: u64, args: &ReapplyArgs) -> Result<(), PodError> {
+    let history = History::open()?;
+    let entry = history.get(id)?;
+    reapply(config, &entry, args)?;
+    history.set_current(entry.id)
+}
+
+/// Sets the wallpaper set before (negative offset) or after the current one again
+pub fn step(config: &Config, offset: isize, args: &ReapplyArgs) -> Result<(), PodError> {
+    let history = History::open()?;
+    let entries = history.entries()?;
+    let current = history
+        .current()?
+        .and_then(|id| entries.iter().position(|entry| entry.id == id))
+        .ok_or_else(|| PodError::History("No wallpaper set yet".to_string()))?;
+    let entry = current
+        .checked_add_signed(offset)
+        .and_then(|index| entries.get(index))
+        .ok_or_else(|| {
+            let direction = if offset < 0 { "earlier" } else { "later" };
+            PodError::History(format!("No {} wallpaper", direction))
+        })?;
+    reapply(config, entry, args)?;
+    history.set_current(entry.id)
+}
+
+fn reapply(config: &Config, entry: &HistoryEntry, args: &ReapplyArgs) -> Result<(), PodError> {
+    println!(
+        "Setting {} of {} again: {}",
+        entry.id,
+        entry.timestamp.format("%Y-%m-%d"),
+        entry.title.as_deref().unwrap_or("-")
+    );
+    if !args.remodify {
+        return pipeline::set_wallpaper(&entry.path.to_string_lossy());
+    }
+
+    let original_path = entry
+        .original_path
+        .as_ref()
+        .ok_or_else(|| PodError::History(format!("Entry {} has no original picture", entry.id)))?;
+    let mut image = image::open(original_path)
+        .map_err(|err| PodError::ImageDecode(format!("{}: {}", original_path.display(), err)))?;
+    pipeline::apply_modifiers(config, &mut image, entry.get_metadata())?;
+    let path = config.get_state_file_name(&format!("{}.jpg", entry.provider));
+    pipeline::save(&image, &path)?;
+    pipeline::set_wallpaper(&path)
+}
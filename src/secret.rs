@@ -0,0 +1,73 @@
+use crate::error::PodError;
+
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Api key or token, redacted from debug output so it doesn't end up in logs
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Plain value, only to be used where the secret is sent
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Reads the secret from the first line of a file.
+    /// On unix the file must not be accessible by group or others, like an ssh key.
+    pub fn from_file(path: &Path) -> Result<Secret, PodError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path)
+                .map_err(|err| {
+                    PodError::Config(format!("Failed to read {}: {}", path.display(), err))
+                })?
+                .permissions()
+                .mode();
+            if mode & 0o077 != 0 {
+                return Err(PodError::Config(format!(
+                    "{} is accessible by other users (mode {:o}), restrict it with chmod 600",
+                    path.display(),
+                    mode & 0o777
+                )));
+            }
+        }
+        let text = fs::read_to_string(path).map_err(|err| {
+            PodError::Config(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        let value = text.lines().next().unwrap_or_default().trim();
+        if value.is_empty() {
+            return Err(PodError::Config(format!("{} is empty", path.display())));
+        }
+        Ok(Secret(value.to_string()))
+    }
+
+    /// Secret given directly (argument, environment or config file) or read from a key file
+    pub fn resolve(
+        name: &str,
+        value: &Option<Secret>,
+        file: &Option<impl AsRef<Path>>,
+    ) -> Result<Secret, PodError> {
+        match (value, file) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(file)) => Secret::from_file(file.as_ref()),
+            (None, None) => Err(PodError::Config(format!("{} is not set", name))),
+        }
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Secret(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
@@ -0,0 +1,298 @@
+use crate::config::{Command, Config, Provider, TimerArgs, UnitArgs};
+use crate::error::PodError;
+use crate::secret::Secret;
+
+use clap::Parser;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes `<name>.service` running pod with the options of the command line and `<name>.timer` starting it.
+/// `argv` is the whole pod command line including the program name, options given before `install-timer`
+/// and the provider after it are embedded as typed. The working directory is the current one
+/// so relative paths and the saved picture stay where they are.
+pub fn install(args: &TimerArgs, argv: &[String]) -> Result<Vec<PathBuf>, PodError> {
+    let global_args = match argv.iter().position(|arg| arg == "install-timer") {
+        Some(pos) => &argv[1..pos],
+        None => &argv[0..0],
+    };
+    let pod_args: Vec<String> = global_args
+        .iter()
+        .chain(args.provider.iter())
+        .cloned()
+        .collect();
+    // the unit would fail on every run with arguments pod doesn't understand
+    let pod_config = Config::try_parse_from(argv.iter().take(1).chain(pod_args.iter()))
+        .map_err(|err| PodError::Config(format!("Invalid pod arguments: {}", err)))?;
+    // units are plain files, keys belong in a key file
+    if let Some(Command::Provider(provider)) = &pod_config.command {
+        let mut keys = Vec::new();
+        collect_api_keys(provider, &mut keys);
+        let embeds_key = keys.iter().any(|key| {
+            !key.expose().is_empty() && pod_args.iter().any(|arg| arg.contains(key.expose()))
+        });
+        if embeds_key {
+            return Err(PodError::Config(
+                "The nasa api key would be written into the unit, \
+                 pass it with --nasa-api-key-file or POD_NASA_API_KEY_FILE instead"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let exe = env::current_exe()
+        .map_err(|err| PodError::Config(format!("Pod executable not found: {}", err)))?;
+    let working_dir = env::current_dir()
+        .map_err(|err| PodError::Config(format!("Current directory is not available: {}", err)))?;
+    let exec_start: Vec<String> = std::iter::once(exe.to_string_lossy().to_string())
+        .chain(pod_args)
+        .map(|arg| quote(&arg))
+        .collect();
+
+    let service = format!(
+        "[Unit]\n\
+         Description=Set the picture of the day as wallpaper\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         WorkingDirectory={}\n\
+         ExecStart={}\n",
+        working_dir.to_string_lossy().replace('%', "%%"),
+        exec_start.join(" ")
+    );
+    let timer = format!(
+        "[Unit]\n\
+         Description=Refresh the picture of the day wallpaper\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         Persistent={}\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        args.on_calendar, args.persistent
+    );
+
+    let unit_dir = get_unit_dir(&args.unit)?;
+    fs::create_dir_all(&unit_dir)
+        .map_err(|err| PodError::Save(format!("{}: {}", unit_dir.display(), err)))?;
+    let mut paths = Vec::new();
+    for (extension, content) in [("service", service), ("timer", timer)] {
+        let path = unit_dir.join(format!("{}.{}", args.unit.unit_name, extension));
+        write_private(&path, &content)
+            .map_err(|err| PodError::Save(format!("{}: {}", path.display(), err)))?;
+        println!("Wrote {}", path.display());
+        paths.push(path);
+    }
+    println!(
+        "Enable with: systemctl --user daemon-reload && systemctl --user enable --now {}.timer",
+        args.unit.unit_name
+    );
+    Ok(paths)
+}
+
+/// Removes the service, the timer and the link enabling the timer, returns the removed files
+pub fn uninstall(args: &UnitArgs) -> Result<Vec<PathBuf>, PodError> {
+    let unit_dir = get_unit_dir(args)?;
+    let timer = format!("{}.timer", args.unit_name);
+    let candidates = [
+        unit_dir.join("timers.target.wants").join(&timer),
+        unit_dir.join(&timer),
+        unit_dir.join(format!("{}.service", args.unit_name)),
+    ];
+    let mut removed = Vec::new();
+    for path in candidates {
+        // the enabling link is checked itself, its target may be gone already
+        if fs::symlink_metadata(&path).is_err() {
+            continue;
+        }
+        fs::remove_file(&path)
+            .map_err(|err| PodError::Save(format!("{}: {}", path.display(), err)))?;
+        println!("Removed {}", path.display());
+        removed.push(path);
+    }
+    if removed.is_empty() {
+        return Err(PodError::Config(format!(
+            "No {} units in {}",
+            args.unit_name,
+            unit_dir.display()
+        )));
+    }
+    println!("Stop a running timer with: systemctl --user stop {}", timer);
+    Ok(removed)
+}
+
+/// Api keys of a provider and the providers it wraps
+fn collect_api_keys<'a>(provider: &'a Provider, keys: &mut Vec<&'a Secret>) {
+    match provider {
+        Provider::Nasa {
+            nasa_api_key: Some(key),
+            ..
+        } => keys.push(key),
+        Provider::Fallback { providers } => {
+            for provider in providers {
+                collect_api_keys(provider, keys);
+            }
+        }
+        Provider::Random { providers } => {
+            for weighted in providers {
+                collect_api_keys(&weighted.provider, keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes a file only readable by the user, the units show the whole pod command line
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content.as_bytes())
+}
+
+fn get_unit_dir(args: &UnitArgs) -> Result<PathBuf, PodError> {
+    args.unit_dir
+        .clone()
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("systemd").join("user")))
+        .ok_or_else(|| {
+            PodError::Config("User config directory not found, pass --unit-dir".to_string())
+        })
+}
+
+/// Quotes a word of a unit file command line, `%` and `$` would be expanded by systemd
+fn quote(word: &str) -> String {
+    let escaped = word.replace('%', "%%").replace('$', "$$");
+    let plain = !escaped.is_empty()
+        && escaped
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@+,%$".contains(c));
+    if plain {
+        escaped
+    } else {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory in the temp directory, unique per test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pod-timer-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn parse(argv: &[&str]) -> (TimerArgs, Vec<String>) {
+        let argv: Vec<String> = argv.iter().map(|arg| arg.to_string()).collect();
+        match Config::try_parse_from(&argv).unwrap().command {
+            Some(Command::InstallTimer(args)) => (args, argv),
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn install_writes_service_and_timer() {
+        let dir = temp_dir("install");
+        let unit_dir = dir.to_string_lossy().to_string();
+        let (args, argv) = parse(&[
+            "pod",
+            "--add-metadata",
+            "true",
+            "install-timer",
+            "--unit-dir",
+            &unit_dir,
+            "--on-calendar",
+            "*-*-* 07:30",
+            "--persistent",
+            "false",
+            "local",
+            "--dir",
+            "my pictures",
+        ]);
+        let paths = install(&args, &argv).unwrap();
+        assert_eq!(paths, [dir.join("pod.service"), dir.join("pod.timer")]);
+
+        let service = fs::read_to_string(dir.join("pod.service")).unwrap();
+        let exec_start = service
+            .lines()
+            .find(|line| line.starts_with("ExecStart="))
+            .unwrap();
+        assert!(exec_start.ends_with(" --add-metadata true local --dir \"my pictures\""));
+        let timer = fs::read_to_string(dir.join("pod.timer")).unwrap();
+        assert!(timer.contains("OnCalendar=*-*-* 07:30\n"));
+        assert!(timer.contains("Persistent=false\n"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for path in paths {
+                let mode = fs::metadata(path).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn install_refuses_api_key() {
+        let dir = temp_dir("key");
+        let unit_dir = dir.to_string_lossy().to_string();
+        for provider in [
+            &["nasa", "--nasa-api-key", "SECRETKEY"][..],
+            &["nasa", "--nasa-api-key=SECRETKEY"],
+            &["random", "bing", "nasa --nasa-api-key SECRETKEY"],
+        ] {
+            let mut argv = vec!["pod", "install-timer", "--unit-dir", &unit_dir];
+            argv.extend(provider);
+            let (args, argv) = parse(&argv);
+            assert!(!format!("{:?}", args).contains("SECRETKEY"));
+            assert!(matches!(install(&args, &argv), Err(PodError::Config(_))));
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn uninstall_removes_units_and_link() {
+        let dir = temp_dir("uninstall");
+        let unit_dir = dir.to_string_lossy().to_string();
+        let (args, argv) = parse(&["pod", "install-timer", "--unit-dir", &unit_dir, "bing"]);
+        install(&args, &argv).unwrap();
+        let wants = dir.join("timers.target.wants");
+        fs::create_dir(&wants).unwrap();
+        std::os::unix::fs::symlink(dir.join("pod.timer"), wants.join("pod.timer")).unwrap();
+
+        let removed = uninstall(&args.unit).unwrap();
+        assert_eq!(
+            removed,
+            [
+                wants.join("pod.timer"),
+                dir.join("pod.timer"),
+                dir.join("pod.service")
+            ]
+        );
+        assert!(fs::symlink_metadata(wants.join("pod.timer")).is_err());
+        assert!(matches!(uninstall(&args.unit), Err(PodError::Config(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn quote_escapes_systemd_specifiers() {
+        assert_eq!(quote("--market"), "--market");
+        assert_eq!(quote("100%"), "100%%");
+        assert_eq!(quote("$HOME"), "$$HOME");
+        assert_eq!(quote("my pictures"), "\"my pictures\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote(""), "\"\"");
+    }
+}